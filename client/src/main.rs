@@ -2,8 +2,13 @@ use std::env;
 use std::net::TcpStream;
 use std::process::exit;
 
-use message::message::{FragmentRequest, Message};
+use message::message::{FragmentError, FragmentRequest, FragmentTask, Message, U8Data};
+use message::scene::Scene;
 use message::send_message::{read_message, send_message};
+use message::terminal::{render_pixels, terminal_columns, TerminalMode};
+
+const USAGE: &str = "Usage: client [name] [address] | client --local <scene.json|image.png> \
+[--preview truecolor|ascii|braille]";
 
 /// Send the request to the server to get the fragment task
 /// with the worker name and the maximal work load
@@ -17,13 +22,13 @@ fn on_message_send_request(_stream: &mut TcpStream, name: String) {
 }
 
 /// Send the result of the fragment task with the data to the server vec pixel intensity
-/// transformed to vec u8
+/// transformed to vec u8, or the error of the fragment task with its id as data
 fn on_message_send_result(
     _stream: &mut TcpStream,
     message_send: Message,
     data: Option<Vec<u8>>,
 ) -> &mut TcpStream {
-    return send_message(_stream, message_send, data, true);
+    send_message(_stream, message_send, data, true)
 }
 
 /// Loop to read the message from the server
@@ -40,9 +45,20 @@ fn loop_message(mut stream: &mut TcpStream) {
                             exit(1);
                         }
                     };
-                    let (fragment_result, data_result) = task.calculate_fractal(id_data);
-                    let message_send: Message = Message::FragmentResult(fragment_result);
-                    stream = on_message_send_result(stream, message_send, Some(data_result));
+                    stream = match task.calculate_fractal(id_data.clone()) {
+                        Ok((fragment_result, data_result)) => {
+                            let message_send: Message = Message::FragmentResult(fragment_result);
+                            on_message_send_result(stream, message_send, Some(data_result))
+                        }
+                        Err(err) => {
+                            println!("Cannot calculate the fragment: {}", err);
+                            let message_send = Message::FragmentError(FragmentError {
+                                id: task.id,
+                                message: err,
+                            });
+                            on_message_send_result(stream, message_send, Some(id_data))
+                        }
+                    };
                 }
                 _ => {
                     println!("Is not a client message");
@@ -57,16 +73,85 @@ fn loop_message(mut stream: &mut TcpStream) {
     }
 }
 
+/// Calculate a whole scene in this process, without a server, and draw it in the terminal
+/// with the given mode
+fn render_locally(scene_path: &str, mode: TerminalMode) {
+    let scene = match Scene::load(scene_path) {
+        Ok(scene) => scene,
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    };
+    let task = FragmentTask {
+        id: U8Data {
+            offset: 0,
+            count: 16,
+        },
+        max_iteration: scene.max_iteration,
+        resolution: scene.resolution.clone(),
+        range: scene.range.clone(),
+        fractal: scene.fractal.clone(),
+        plane: scene.plane,
+        supersampling: scene.supersampling,
+        center: scene.center,
+    };
+    let pixels = match task.calculate_pixels() {
+        Ok(pixels) => pixels,
+        Err(err) => {
+            println!("Cannot calculate the scene: {}", err);
+            exit(1);
+        }
+    };
+    match render_pixels(&pixels, &scene, terminal_columns(), mode) {
+        Ok(text) => print!("{}", text),
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    }
+}
+
 /// Main function to connect to the server
-/// and send the request to the server
+/// and send the request to the server,
+/// or with `--local <scene>`, to render a scene in the terminal without a server
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let name = if &args.len() >= &1 {
+    if args.get(1).map(String::as_str) == Some("--local") {
+        let scene_path = match args.get(2) {
+            Some(scene_path) => scene_path,
+            None => {
+                println!("{}", USAGE);
+                exit(1);
+            }
+        };
+        let mode = match args.get(3).map(String::as_str) {
+            None => TerminalMode::default(),
+            Some("--preview") => match args.get(4).map(|mode| mode.parse()) {
+                Some(Ok(mode)) => mode,
+                Some(Err(err)) => {
+                    println!("{}", err);
+                    exit(1);
+                }
+                None => {
+                    println!("{}", USAGE);
+                    exit(1);
+                }
+            },
+            Some(_) => {
+                println!("{}", USAGE);
+                exit(1);
+            }
+        };
+        render_locally(scene_path, mode);
+        return;
+    }
+    let name = if args.len() > 1 {
         String::from(&args[1])
     } else {
         String::from("hello")
     };
-    let ip_address = if &args.len() >= &2 {
+    let ip_address = if args.len() > 2 {
         String::from(&args[2])
     } else {
         String::from("localhost:8787")
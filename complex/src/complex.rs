@@ -1,94 +1,260 @@
+use crate::float::Float;
 use serde::{Deserialize, Serialize};
-use std::ops::{Add, Div, Mul, Sub};
-
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
-pub struct Complex {
-    pub re: f64,
-    pub im: f64,
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Complex number generic over its floating point type, f64 by default.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct Complex<T = f64> {
+    pub re: T,
+    pub im: T,
 }
-impl Complex {
-    pub fn new(re: f64, im: f64) -> Complex {
+impl<T: Float> Complex<T> {
+    pub fn new(re: T, im: T) -> Complex<T> {
         Complex { re, im }
     }
 
-    pub fn sub_reel(self, scalar: f64) -> Complex {
+    /// Build a complex number from its modulus and argument
+    pub fn from_polar(r: T, theta: T) -> Complex<T> {
+        Complex {
+            re: r * theta.cos(),
+            im: r * theta.sin(),
+        }
+    }
+
+    /// Return the modulus and the argument
+    pub fn to_polar(self) -> (T, T) {
+        (self.norm(), self.arg())
+    }
+
+    pub fn sub_reel(self, scalar: T) -> Complex<T> {
         Complex {
             re: self.re - scalar,
             im: self.im,
         }
     }
 
-    pub fn mul_reel(self, scalar: f64) -> Complex {
+    pub fn mul_reel(self, scalar: T) -> Complex<T> {
         Complex {
             re: self.re * scalar,
             im: self.im * scalar,
         }
     }
 
-    pub fn square(&self) -> Complex {
+    pub fn square(&self) -> Complex<T> {
         Complex {
             re: self.re * self.re - self.im * self.im,
-            im: 2.0 * self.re * self.im,
+            im: T::TWO * self.re * self.im,
         }
     }
 
-    pub fn add(&self, other: Complex) -> Complex {
+    pub fn add(&self, other: Complex<T>) -> Complex<T> {
         Complex {
             re: self.re + other.re,
             im: self.im + other.im,
         }
     }
 
-    pub fn add_i64(self, other: i64) -> Complex {
+    pub fn add_i64(self, other: i64) -> Complex<T> {
         Complex {
-            re: self.re + other as f64,
+            re: self.re + T::from_f64(other as f64),
             im: self.im,
         }
     }
 
-    pub fn sin(self) -> Complex {
+    pub fn sin(self) -> Complex<T> {
         Complex {
             re: self.re.sin() * self.im.cosh(),
             im: self.re.cos() * self.im.sinh(),
         }
     }
 
-    pub fn cube(&self) -> Complex {
+    pub fn cos(self) -> Complex<T> {
+        Complex {
+            re: self.re.cos() * self.im.cosh(),
+            im: -(self.re.sin() * self.im.sinh()),
+        }
+    }
+
+    /// Tangent, computed as `-i tanh(iz)`
+    pub fn tan(self) -> Complex<T> {
+        let tanh = Complex::new(-self.im, self.re).tanh();
+        Complex::new(tanh.im, -tanh.re)
+    }
+
+    pub fn sinh(self) -> Complex<T> {
+        Complex {
+            re: self.re.sinh() * self.im.cos(),
+            im: self.re.cosh() * self.im.sin(),
+        }
+    }
+
+    pub fn cosh(self) -> Complex<T> {
+        Complex {
+            re: self.re.cosh() * self.im.cos(),
+            im: self.re.sinh() * self.im.sin(),
+        }
+    }
+
+    /// Hyperbolic tangent, tending to ±1 instead of overflowing for large real parts
+    pub fn tanh(self) -> Complex<T> {
+        let two_re = T::TWO * self.re;
+        let two_im = T::TWO * self.im;
+        let denominator = two_re.cosh() + two_im.cos();
+        if denominator.is_infinite() && !self.im.is_infinite() {
+            return Complex::new(T::ONE.copysign(self.re), T::ZERO.copysign(self.im));
+        }
         Complex {
-            re: self.re * (self.re * self.re - 3.0 * self.im * self.im),
-            im: self.im * (3.0 * self.re * self.re - self.im * self.im),
+            re: two_re.sinh() / denominator,
+            im: two_im.sin() / denominator,
         }
     }
 
-    pub fn pow(&self, n: u32) -> Complex {
-        let mut result = Complex::new(1.0, 0.0);
-        for _ in 0..n {
-            result = result * *self;
+    pub fn exp(self) -> Complex<T> {
+        let modulus = self.re.exp();
+        if self.im == T::ZERO {
+            // Keep exp(+inf) = +inf instead of inf * sin(0) = NaN
+            return Complex::new(modulus, self.im);
+        }
+        Complex::from_polar(modulus, self.im)
+    }
+
+    /// Principal logarithm, with the imaginary part in ]-π, π]
+    pub fn ln(self) -> Complex<T> {
+        Complex {
+            re: self.re.hypot(self.im).ln(),
+            im: self.arg(),
+        }
+    }
+
+    /// Principal square root, with a non-negative real part and the branch cut on the
+    /// negative real axis (the sign of a zero imaginary part selects the side)
+    pub fn sqrt(self) -> Complex<T> {
+        if self.re == T::ZERO && self.im == T::ZERO {
+            return Complex::new(T::ZERO, self.im);
+        }
+        if self.im.is_infinite() {
+            return Complex::new(self.im.abs(), self.im);
+        }
+        let half = T::from_f64(0.5);
+        let modulus = self.re.hypot(self.im);
+        if self.re >= T::ZERO {
+            let t = ((modulus + self.re) * half).sqrt();
+            Complex::new(t, self.im / (T::TWO * t))
+        } else {
+            let t = ((modulus - self.re) * half).sqrt();
+            Complex::new(self.im.abs() / (T::TWO * t), t.copysign(self.im))
+        }
+    }
+
+    /// Principal value of `self^exponent` for a real exponent
+    pub fn powf(self, exponent: T) -> Complex<T> {
+        if self.re == T::ZERO && self.im == T::ZERO {
+            return Self::pow_of_zero(exponent);
+        }
+        let (r, theta) = self.to_polar();
+        Complex::from_polar(r.powf(exponent), theta * exponent)
+    }
+
+    /// Principal value of `self^exponent` for a complex exponent, `exp(exponent * ln(self))`
+    pub fn powc(self, exponent: Complex<T>) -> Complex<T> {
+        if self.re == T::ZERO && self.im == T::ZERO {
+            return if exponent.im == T::ZERO {
+                Self::pow_of_zero(exponent.re)
+            } else {
+                Complex::new(T::from_f64(f64::NAN), T::from_f64(f64::NAN))
+            };
+        }
+        (exponent * self.ln()).exp()
+    }
+
+    fn pow_of_zero(exponent: T) -> Complex<T> {
+        if exponent > T::ZERO {
+            Complex::new(T::ZERO, T::ZERO)
+        } else if exponent == T::ZERO {
+            Complex::new(T::ONE, T::ZERO)
+        } else {
+            Complex::new(T::from_f64(f64::INFINITY), T::ZERO)
+        }
+    }
+
+    pub fn cube(&self) -> Complex<T> {
+        let three = T::from_f64(3.0);
+        Complex {
+            re: self.re * (self.re * self.re - three * self.im * self.im),
+            im: self.im * (three * self.re * self.re - self.im * self.im),
+        }
+    }
+
+    pub fn pow(&self, n: u32) -> Complex<T> {
+        let mut base = *self;
+        let mut exp = n;
+        let mut result = Complex::new(T::ONE, T::ZERO);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result *= base;
+            }
+            base = base.square();
+            exp >>= 1;
         }
         result
     }
 
-    pub fn arg(&self) -> f64 {
+    /// Raise to an integer power by binary exponentiation, negative powers use the reciprocal
+    pub fn powi(&self, n: i32) -> Complex<T> {
+        let result = self.pow(n.unsigned_abs());
+        if n < 0 {
+            result.recip()
+        } else {
+            result
+        }
+    }
+
+    pub fn conj(&self) -> Complex<T> {
+        Complex {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+
+    pub fn recip(&self) -> Complex<T> {
+        let denominator = self.norm_squared();
+        Complex {
+            re: self.re / denominator,
+            im: -self.im / denominator,
+        }
+    }
+
+    pub fn arg(&self) -> T {
         self.im.atan2(self.re)
     }
 
-    pub fn norm_squared(&self) -> f64 {
+    pub fn norm_squared(&self) -> T {
         self.re * self.re + self.im * self.im
     }
 
-    pub fn norm(&self) -> f64 {
+    pub fn norm(&self) -> T {
         (self.re * self.re + self.im * self.im).sqrt()
     }
 
-    pub fn pow4(&self) -> Complex {
+    pub fn pow4(&self) -> Complex<T> {
         self.cube() * *self
     }
+
+    pub fn is_nan(&self) -> bool {
+        self.re.is_nan() || self.im.is_nan()
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        !self.is_nan() && (self.re.is_infinite() || self.im.is_infinite())
+    }
 }
 
-impl Add for Complex {
-    type Output = Complex;
+impl<T: Float> Add for Complex<T> {
+    type Output = Complex<T>;
 
-    fn add(self, other: Complex) -> Complex {
+    fn add(self, other: Complex<T>) -> Complex<T> {
         Complex {
             re: self.re + other.re,
             im: self.im + other.im,
@@ -96,30 +262,30 @@ impl Add for Complex {
     }
 }
 
-impl Mul<Complex> for Complex {
-    type Output = Complex;
+impl<T: Float> Mul<Complex<T>> for Complex<T> {
+    type Output = Complex<T>;
 
-    fn mul(self, other: Complex) -> Complex {
+    fn mul(self, other: Complex<T>) -> Complex<T> {
         let real = self.re * other.re - self.im * other.im;
         let imag = self.re * other.im + self.im * other.re;
         Complex::new(real, imag)
     }
 }
 
-impl Sub<Complex> for Complex {
-    type Output = Complex;
+impl<T: Float> Sub<Complex<T>> for Complex<T> {
+    type Output = Complex<T>;
 
-    fn sub(self, other: Complex) -> Complex {
+    fn sub(self, other: Complex<T>) -> Complex<T> {
         let real = self.re - other.re;
         let imag = self.im - other.im;
         Complex::new(real, imag)
     }
 }
 
-impl Div<Complex> for Complex {
-    type Output = Complex;
+impl<T: Float> Div<Complex<T>> for Complex<T> {
+    type Output = Complex<T>;
 
-    fn div(self, other: Complex) -> Complex {
+    fn div(self, other: Complex<T>) -> Complex<T> {
         let denominator = other.re * other.re + other.im * other.im;
         let real = (self.re * other.re + self.im * other.im) / denominator;
         let imag = (self.im * other.re - self.re * other.im) / denominator;
@@ -127,6 +293,156 @@ impl Div<Complex> for Complex {
     }
 }
 
+impl<T: Float> Neg for Complex<T> {
+    type Output = Complex<T>;
+
+    fn neg(self) -> Complex<T> {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl<T: Float> Add<T> for Complex<T> {
+    type Output = Complex<T>;
+
+    fn add(self, scalar: T) -> Complex<T> {
+        Complex::new(self.re + scalar, self.im)
+    }
+}
+
+impl<T: Float> Sub<T> for Complex<T> {
+    type Output = Complex<T>;
+
+    fn sub(self, scalar: T) -> Complex<T> {
+        self.sub_reel(scalar)
+    }
+}
+
+impl<T: Float> Mul<T> for Complex<T> {
+    type Output = Complex<T>;
+
+    fn mul(self, scalar: T) -> Complex<T> {
+        self.mul_reel(scalar)
+    }
+}
+
+impl<T: Float> Div<T> for Complex<T> {
+    type Output = Complex<T>;
+
+    fn div(self, scalar: T) -> Complex<T> {
+        Complex::new(self.re / scalar, self.im / scalar)
+    }
+}
+
+/// Implement the `*Assign` operators from the binary operators, for both complex and scalar operands
+macro_rules! impl_assign {
+    ($trait:ident, $method:ident, $operator:tt) => {
+        impl<T: Float> $trait<Complex<T>> for Complex<T> {
+            fn $method(&mut self, other: Complex<T>) {
+                *self = *self $operator other;
+            }
+        }
+
+        impl<T: Float> $trait<T> for Complex<T> {
+            fn $method(&mut self, scalar: T) {
+                *self = *self $operator scalar;
+            }
+        }
+    };
+}
+
+impl_assign!(AddAssign, add_assign, +);
+impl_assign!(SubAssign, sub_assign, -);
+impl_assign!(MulAssign, mul_assign, *);
+impl_assign!(DivAssign, div_assign, /);
+
+/// Implement the operators with the scalar on the left side, which must be done per float type
+macro_rules! impl_scalar_left {
+    ($type:ty) => {
+        impl Add<Complex<$type>> for $type {
+            type Output = Complex<$type>;
+
+            fn add(self, other: Complex<$type>) -> Complex<$type> {
+                other + self
+            }
+        }
+
+        impl Sub<Complex<$type>> for $type {
+            type Output = Complex<$type>;
+
+            fn sub(self, other: Complex<$type>) -> Complex<$type> {
+                Complex::new(self - other.re, -other.im)
+            }
+        }
+
+        impl Mul<Complex<$type>> for $type {
+            type Output = Complex<$type>;
+
+            fn mul(self, other: Complex<$type>) -> Complex<$type> {
+                other * self
+            }
+        }
+
+        impl Div<Complex<$type>> for $type {
+            type Output = Complex<$type>;
+
+            fn div(self, other: Complex<$type>) -> Complex<$type> {
+                Complex::new(self, 0.0) / other
+            }
+        }
+    };
+}
+
+impl_scalar_left!(f32);
+impl_scalar_left!(f64);
+
+/// Written as `a+bi` or `a-bi`
+impl<T: Float> fmt::Display for Complex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im < T::ZERO || (self.im == T::ZERO && self.im.to_f64().is_sign_negative()) {
+            write!(f, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+/// Parses `a+bi`, `a-bi`, `a`, `bi` and `i`, with optional exponents such as `1e-3-2.5e2i`
+impl<T: Float> FromStr for Complex<T> {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Complex<T>, String> {
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let parse_part = |part: &str| -> Result<T, String> {
+            part.parse::<T>()
+                .map_err(|_| format!("invalid complex number {text:?}"))
+        };
+        let parse_imaginary = |part: &str| -> Result<T, String> {
+            match part {
+                "" | "+" => Ok(T::ONE),
+                "-" => Ok(-T::ONE),
+                _ => parse_part(part),
+            }
+        };
+
+        let Some(imaginary) = compact.strip_suffix('i') else {
+            return Ok(Complex::new(parse_part(&compact)?, T::ZERO));
+        };
+        // The sign separating both parts is the last one that does not follow an exponent
+        let bytes = imaginary.as_bytes();
+        let split = (1..bytes.len()).rev().find(|&index| {
+            (bytes[index] == b'+' || bytes[index] == b'-')
+                && !matches!(bytes[index - 1], b'e' | b'E')
+        });
+        match split {
+            Some(index) => Ok(Complex::new(
+                parse_part(&imaginary[..index])?,
+                parse_imaginary(&imaginary[index..])?,
+            )),
+            None => Ok(Complex::new(T::ZERO, parse_imaginary(imaginary)?)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -257,4 +573,207 @@ mod tests {
         let deserialized: super::Complex = serde_json::from_str(&serialized).unwrap();
         assert_eq!(first, deserialized);
     }
+
+    #[test]
+    fn test_f32() {
+        let first: super::Complex<f32> = super::Complex::new(1.0, 2.0);
+        let result = first * first;
+        assert_eq!(result, super::Complex::new(-3.0f32, 4.0f32));
+    }
+
+    #[test]
+    fn test_neg_and_conj() {
+        let first = super::Complex::new(1.0, 2.0);
+        assert_eq!(-first, super::Complex::new(-1.0, -2.0));
+        assert_eq!(first.conj(), super::Complex::new(1.0, -2.0));
+    }
+
+    #[test]
+    fn test_assign_operators() {
+        let mut result = super::Complex::new(1.0, 2.0);
+        result += super::Complex::new(3.0, 4.0);
+        assert_eq!(result, super::Complex::new(4.0, 6.0));
+        result -= 1.0;
+        assert_eq!(result, super::Complex::new(3.0, 6.0));
+        result *= 2.0;
+        assert_eq!(result, super::Complex::new(6.0, 12.0));
+        result /= super::Complex::new(0.0, 3.0);
+        assert_eq!(result, super::Complex::new(4.0, -2.0));
+    }
+
+    #[test]
+    fn test_scalar_operators() {
+        let first = super::Complex::new(1.0, 2.0);
+        assert_eq!(first + 1.0, super::Complex::new(2.0, 2.0));
+        assert_eq!(1.0 - first, super::Complex::new(0.0, -2.0));
+        assert_eq!(2.0 * first, super::Complex::new(2.0, 4.0));
+        assert_eq!(first / 2.0, super::Complex::new(0.5, 1.0));
+        assert_eq!(5.0 / first, super::Complex::new(1.0, -2.0));
+    }
+
+    #[test]
+    fn test_recip() {
+        let first = super::Complex::new(3.0, 4.0);
+        assert_eq!(first.recip(), super::Complex::new(0.12, -0.16));
+    }
+
+    #[test]
+    fn test_polar() {
+        let first = super::Complex::from_polar(2.0, std::f64::consts::FRAC_PI_2);
+        assert!((first - super::Complex::new(0.0, 2.0)).norm() < 1e-15);
+        let (r, theta) = super::Complex::new(0.0, 2.0).to_polar();
+        assert_eq!(r, 2.0);
+        assert_eq!(theta, std::f64::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn test_powi() {
+        let first = super::Complex::new(1.0, 2.0);
+        assert_eq!(first.powi(0), super::Complex::new(1.0, 0.0));
+        assert_eq!(first.powi(4), first.pow4());
+        assert_eq!(first.powi(7), first.pow4() * first.cube());
+        let inverse = first.powi(-2) * first.square();
+        assert!((inverse - super::Complex::new(1.0, 0.0)).norm() < 1e-15);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(super::Complex::new(1.5, -2.0).to_string(), "1.5-2i");
+        assert_eq!(super::Complex::new(-1.0, 0.25).to_string(), "-1+0.25i");
+    }
+
+    #[test]
+    fn test_from_str() {
+        let parse = |text: &str| text.parse::<super::Complex>();
+        assert_eq!(parse("1.5-2i"), Ok(super::Complex::new(1.5, -2.0)));
+        assert_eq!(parse(" -1 + 0.25i "), Ok(super::Complex::new(-1.0, 0.25)));
+        assert_eq!(parse("3"), Ok(super::Complex::new(3.0, 0.0)));
+        assert_eq!(parse("-i"), Ok(super::Complex::new(0.0, -1.0)));
+        assert_eq!(parse("2.5i"), Ok(super::Complex::new(0.0, 2.5)));
+        assert_eq!(parse("1e-3-2e+2i"), Ok(super::Complex::new(0.001, -200.0)));
+        assert!(parse("1+2j").is_err());
+        let first = super::Complex::new(0.285, -0.013);
+        assert_eq!(parse(&first.to_string()), Ok(first));
+    }
+
+    fn assert_close(left: super::Complex, right: super::Complex) {
+        assert!((left - right).norm() < 1e-12, "{left} != {right}");
+    }
+
+    #[test]
+    fn test_exp_ln_identities() {
+        let first = super::Complex::new(1.0, 2.0);
+        assert_close(first.ln().exp(), first);
+        assert_close(first.exp().ln(), first);
+        assert_close(first.exp() * (-first).exp(), super::Complex::new(1.0, 0.0));
+        let euler = super::Complex::new(0.0, std::f64::consts::PI).exp();
+        assert_close(euler, super::Complex::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_trigonometric_identities() {
+        let first = super::Complex::new(0.7, -1.3);
+        let one = super::Complex::new(1.0, 0.0);
+        assert_close(first.sin().square() + first.cos().square(), one);
+        assert_close(first.cosh().square() - first.sinh().square(), one);
+        assert_close(first.tan(), first.sin() / first.cos());
+        assert_close(first.tanh(), first.sinh() / first.cosh());
+        let i_first = super::Complex::new(-first.im, first.re);
+        assert_close(first.cos(), (i_first.exp() + (-i_first).exp()) / 2.0);
+        assert_close(i_first.cosh(), first.cos());
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let first = super::Complex::new(1.0, 2.0);
+        assert_close(first.sqrt().square(), first);
+        assert!(first.sqrt().re > 0.0);
+        assert_eq!(
+            super::Complex::new(4.0, 0.0).sqrt(),
+            super::Complex::new(2.0, 0.0)
+        );
+        assert_eq!(
+            super::Complex::new(0.0, 0.0).sqrt(),
+            super::Complex::new(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_powers() {
+        let first = super::Complex::new(1.0, 2.0);
+        assert_close(first.powf(2.0), first.square());
+        assert_close(first.powf(0.5), first.sqrt());
+        assert_close(first.powc(super::Complex::new(3.0, 0.0)), first.cube());
+        let i = super::Complex::new(0.0, 1.0);
+        let i_pow_i = super::Complex::new((-std::f64::consts::FRAC_PI_2).exp(), 0.0);
+        assert_close(i.powc(i), i_pow_i);
+        let zero = super::Complex::new(0.0, 0.0);
+        assert_eq!(zero.powf(2.5), zero);
+        assert_eq!(
+            zero.powc(super::Complex::new(0.0, 0.0)),
+            super::Complex::new(1.0, 0.0)
+        );
+        assert!(zero.powc(i).is_nan());
+    }
+
+    #[test]
+    fn test_branch_cuts() {
+        let above = super::Complex::new(-4.0, 0.0);
+        let below = super::Complex::new(-4.0, -0.0);
+        assert_eq!(above.sqrt(), super::Complex::new(0.0, 2.0));
+        assert_eq!(below.sqrt(), super::Complex::new(0.0, -2.0));
+        let pi = std::f64::consts::PI;
+        assert_eq!(above.ln().im, pi);
+        assert_eq!(below.ln().im, -pi);
+        assert_close(above.powf(0.5), super::Complex::new(0.0, 2.0));
+        assert_close(below.powf(0.5), super::Complex::new(0.0, -2.0));
+    }
+
+    #[test]
+    fn test_infinities() {
+        let infinity = f64::INFINITY;
+        assert_eq!(
+            super::Complex::new(infinity, 0.0).exp(),
+            super::Complex::new(infinity, 0.0)
+        );
+        assert_eq!(super::Complex::new(-infinity, 1.0).exp().norm(), 0.0);
+        let ln_zero = super::Complex::new(0.0, 0.0).ln();
+        assert_eq!(ln_zero.re, -infinity);
+        assert_eq!(
+            super::Complex::new(1000.0, 1.0).tanh(),
+            super::Complex::new(1.0, 0.0)
+        );
+        assert_eq!(
+            super::Complex::new(-1000.0, -1.0).tanh(),
+            super::Complex::new(-1.0, -0.0)
+        );
+        assert_eq!(
+            super::Complex::new(1.0, 1000.0).tan(),
+            super::Complex::new(0.0, 1.0)
+        );
+        assert_eq!(
+            super::Complex::new(1.0, infinity).sqrt(),
+            super::Complex::new(infinity, infinity)
+        );
+        assert!(super::Complex::new(infinity, 0.0).is_infinite());
+    }
+
+    #[test]
+    fn test_nan_propagation() {
+        let nan = super::Complex::new(f64::NAN, 1.0);
+        assert!(nan.exp().is_nan());
+        assert!(nan.ln().is_nan());
+        assert!(nan.sqrt().is_nan());
+        assert!(nan.cos().is_nan());
+        assert!(nan.tanh().is_nan());
+        assert!(nan.powc(super::Complex::new(2.0, 0.0)).is_nan());
+        assert!(!nan.is_infinite());
+    }
+
+    #[test]
+    fn test_f32_transcendental() {
+        let first: super::Complex<f32> = super::Complex::new(0.5, 0.25);
+        let result = first.ln().exp() - first;
+        assert!(result.norm() < 1e-6);
+    }
 }
@@ -0,0 +1,212 @@
+use crate::complex::Complex;
+use crate::double_double::DoubleDouble;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Complex number with double-double parts, for coordinates that need more precision than f64.
+/// Its parts are serialized as decimal strings.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct ComplexDD {
+    pub re: DoubleDouble,
+    pub im: DoubleDouble,
+}
+
+impl ComplexDD {
+    pub fn new(re: DoubleDouble, im: DoubleDouble) -> ComplexDD {
+        ComplexDD { re, im }
+    }
+
+    /// Round both parts to f64
+    pub fn to_complex(self) -> Complex {
+        Complex::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    pub fn sub_reel(self, scalar: DoubleDouble) -> ComplexDD {
+        ComplexDD {
+            re: self.re - scalar,
+            im: self.im,
+        }
+    }
+
+    pub fn mul_reel(self, scalar: DoubleDouble) -> ComplexDD {
+        ComplexDD {
+            re: self.re * scalar,
+            im: self.im * scalar,
+        }
+    }
+
+    pub fn square(&self) -> ComplexDD {
+        ComplexDD {
+            re: self.re.square() - self.im.square(),
+            im: (self.re * self.im).ldexp(1),
+        }
+    }
+
+    pub fn add(&self, other: ComplexDD) -> ComplexDD {
+        ComplexDD {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+
+    pub fn add_i64(self, other: i64) -> ComplexDD {
+        ComplexDD {
+            re: self.re + DoubleDouble::from(other),
+            im: self.im,
+        }
+    }
+
+    pub fn sin(self) -> ComplexDD {
+        let (sin, cos) = self.re.sin_cos();
+        let (sinh, cosh) = self.im.sinh_cosh();
+        ComplexDD {
+            re: sin * cosh,
+            im: cos * sinh,
+        }
+    }
+
+    pub fn cube(&self) -> ComplexDD {
+        self.square() * *self
+    }
+
+    pub fn pow(&self, n: u32) -> ComplexDD {
+        let mut base = *self;
+        let mut exp = n;
+        let mut result = ComplexDD::new(DoubleDouble::ONE, DoubleDouble::ZERO);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            base = base.square();
+            exp >>= 1;
+        }
+        result
+    }
+
+    pub fn arg(&self) -> DoubleDouble {
+        DoubleDouble::atan2(self.im, self.re)
+    }
+
+    pub fn norm_squared(&self) -> DoubleDouble {
+        self.re.square() + self.im.square()
+    }
+
+    pub fn norm(&self) -> DoubleDouble {
+        self.norm_squared().sqrt()
+    }
+
+    pub fn pow4(&self) -> ComplexDD {
+        self.square().square()
+    }
+}
+
+impl From<Complex> for ComplexDD {
+    fn from(value: Complex) -> ComplexDD {
+        ComplexDD::new(DoubleDouble::from(value.re), DoubleDouble::from(value.im))
+    }
+}
+
+impl Add for ComplexDD {
+    type Output = ComplexDD;
+
+    fn add(self, other: ComplexDD) -> ComplexDD {
+        ComplexDD {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl Mul<ComplexDD> for ComplexDD {
+    type Output = ComplexDD;
+
+    fn mul(self, other: ComplexDD) -> ComplexDD {
+        let real = self.re * other.re - self.im * other.im;
+        let imag = self.re * other.im + self.im * other.re;
+        ComplexDD::new(real, imag)
+    }
+}
+
+impl Sub<ComplexDD> for ComplexDD {
+    type Output = ComplexDD;
+
+    fn sub(self, other: ComplexDD) -> ComplexDD {
+        ComplexDD::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Div<ComplexDD> for ComplexDD {
+    type Output = ComplexDD;
+
+    fn div(self, other: ComplexDD) -> ComplexDD {
+        let denominator = other.norm_squared();
+        let real = (self.re * other.re + self.im * other.im) / denominator;
+        let imag = (self.im * other.re - self.re * other.im) / denominator;
+        ComplexDD::new(real, imag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComplexDD;
+    use crate::complex::Complex;
+    use crate::double_double::DoubleDouble;
+
+    fn complex_dd(re: f64, im: f64) -> ComplexDD {
+        ComplexDD::new(DoubleDouble::from(re), DoubleDouble::from(im))
+    }
+
+    #[test]
+    fn test_multiplication() {
+        let result = complex_dd(1.0, 2.0) * complex_dd(3.0, 4.0);
+        assert_eq!(result, complex_dd(-5.0, 10.0));
+    }
+
+    #[test]
+    fn test_division() {
+        let result = complex_dd(-5.0, 10.0) / complex_dd(3.0, 4.0);
+        assert_eq!(result.to_complex(), Complex::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn test_square_cube_pow() {
+        let first = complex_dd(1.0, 2.0);
+        assert_eq!(first.square(), complex_dd(-3.0, 4.0));
+        assert_eq!(first.cube(), complex_dd(-11.0, -2.0));
+        assert_eq!(first.pow4(), complex_dd(-7.0, -24.0));
+        assert_eq!(first.pow(5), first.pow4() * first);
+    }
+
+    #[test]
+    fn test_sin_matches_f64() {
+        let result = complex_dd(1.0, 2.0).sin().to_complex();
+        let expected = Complex::new(1.0, 2.0).sin();
+        assert!((result - expected).norm() < 1e-14);
+    }
+
+    #[test]
+    fn test_norm_and_arg() {
+        let first = complex_dd(3.0, 4.0);
+        assert_eq!(first.norm(), DoubleDouble::from(5.0));
+        assert!((first.arg().to_f64() - 4f64.atan2(3.0)).abs() < 1e-16);
+    }
+
+    #[test]
+    fn test_keeps_precision_beyond_f64() {
+        let centre: ComplexDD = serde_json::from_str(
+            r#"{"re":"-0.743643887037158704752191506114774","im":"0.131825904205311970493132056385139"}"#,
+        )
+        .unwrap();
+        let shifted = centre.add_i64(1).add_i64(-1);
+        assert_eq!(shifted, centre);
+        assert!(centre.re.lo != 0.0 && centre.im.lo != 0.0);
+    }
+
+    #[test]
+    fn test_serde() {
+        let first: ComplexDD = serde_json::from_str(r#"{"re":"1e-40","im":2.5}"#).unwrap();
+        let serialized = serde_json::to_string(&first).unwrap();
+        let deserialized: ComplexDD = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+    }
+}
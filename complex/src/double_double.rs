@@ -0,0 +1,642 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::f64::consts;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// Number of significant decimal digits written by `Display`, the most a double-double holds.
+const DISPLAY_DIGITS: usize = 31;
+
+/// Largest power of ten multiplied at once when scaling, well inside the range of an f64
+const MAX_POWER_OF_TEN_STEP: i32 = 256;
+
+/// Decimal exponent below which any mantissa rounds to 0, past the smallest subnormal f64
+const MIN_DECIMAL_EXPONENT: i32 = 400;
+
+/// Decimal exponent above which any mantissa overflows, past the largest f64
+const MAX_DECIMAL_EXPONENT: i32 = 310;
+
+/// A double-double number: the unevaluated sum `hi + lo` of two f64 where `|lo| <= ulp(hi) / 2`.
+/// It gives about 106 bits (~32 decimal digits) of mantissa, which is enough to express
+/// deep-zoom coordinates that a single f64 cannot hold.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+/// Error-free sum of two f64: `a + b == s + e` exactly.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    let e = (a - (s - bb)) + (b - bb);
+    (s, e)
+}
+
+/// Error-free sum of two f64, valid when `|a| >= |b|`.
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let e = b - (s - a);
+    (s, e)
+}
+
+/// Error-free product of two f64: `a * b == p + e` exactly.
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    let e = a.mul_add(b, -p);
+    (p, e)
+}
+
+impl DoubleDouble {
+    pub const ZERO: DoubleDouble = DoubleDouble { hi: 0.0, lo: 0.0 };
+    pub const ONE: DoubleDouble = DoubleDouble { hi: 1.0, lo: 0.0 };
+    pub const PI: DoubleDouble = DoubleDouble {
+        hi: consts::PI,
+        lo: 1.2246467991473532e-16,
+    };
+    pub const TAU: DoubleDouble = DoubleDouble {
+        hi: consts::TAU,
+        lo: 2.4492935982947064e-16,
+    };
+    pub const LN_2: DoubleDouble = DoubleDouble {
+        hi: consts::LN_2,
+        lo: 2.3190468138462996e-17,
+    };
+
+    pub fn new(hi: f64, lo: f64) -> DoubleDouble {
+        let (hi, lo) = two_sum(hi, lo);
+        DoubleDouble { hi, lo }
+    }
+
+    /// Round to the nearest f64
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.hi.is_nan() || self.lo.is_nan()
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.hi.is_finite()
+    }
+
+    pub fn abs(self) -> DoubleDouble {
+        if self.hi < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    pub fn floor(self) -> DoubleDouble {
+        let hi = self.hi.floor();
+        if hi == self.hi {
+            let (hi, lo) = quick_two_sum(hi, self.lo.floor());
+            DoubleDouble { hi, lo }
+        } else {
+            DoubleDouble { hi, lo: 0.0 }
+        }
+    }
+
+    pub fn round(self) -> DoubleDouble {
+        (self + DoubleDouble::from(0.5)).floor()
+    }
+
+    /// Multiply by `2^exp`, which is exact
+    pub fn ldexp(self, exp: i32) -> DoubleDouble {
+        let scale = 2f64.powi(exp);
+        DoubleDouble {
+            hi: self.hi * scale,
+            lo: self.lo * scale,
+        }
+    }
+
+    pub fn square(self) -> DoubleDouble {
+        let (p, e) = two_prod(self.hi, self.hi);
+        let e = e + 2.0 * self.hi * self.lo + self.lo * self.lo;
+        let (hi, lo) = quick_two_sum(p, e);
+        DoubleDouble { hi, lo }
+    }
+
+    pub fn sqrt(self) -> DoubleDouble {
+        if self.hi <= 0.0 {
+            return DoubleDouble::from(self.hi.sqrt());
+        }
+        let approx = self.hi.sqrt();
+        let correction = (self - DoubleDouble::from(approx).square()).hi / (2.0 * approx);
+        let (hi, lo) = quick_two_sum(approx, correction);
+        DoubleDouble { hi, lo }
+    }
+
+    /// Raise to an integer power by binary exponentiation
+    pub fn powi(self, n: i32) -> DoubleDouble {
+        let mut base = self;
+        let mut exp = n.unsigned_abs();
+        let mut result = DoubleDouble::ONE;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            base = base.square();
+            exp >>= 1;
+        }
+        if n < 0 {
+            DoubleDouble::ONE / result
+        } else {
+            result
+        }
+    }
+
+    pub fn exp(self) -> DoubleDouble {
+        if !self.is_finite() {
+            return DoubleDouble::from(self.hi.exp());
+        }
+        let k = (self / DoubleDouble::LN_2).round();
+        let r = self - k * DoubleDouble::LN_2;
+        let mut term = DoubleDouble::ONE;
+        let mut sum = DoubleDouble::ONE;
+        for n in 1..40 {
+            term = term * r / DoubleDouble::from(n as f64);
+            sum = sum + term;
+            if term.hi.abs() < 1e-34 * sum.hi.abs() {
+                break;
+            }
+        }
+        sum.ldexp(k.hi as i32)
+    }
+
+    /// Sine and cosine, computed together from their Taylor series after reduction modulo 2π
+    pub fn sin_cos(self) -> (DoubleDouble, DoubleDouble) {
+        if !self.is_finite() {
+            return (DoubleDouble::from(f64::NAN), DoubleDouble::from(f64::NAN));
+        }
+        let k = (self / DoubleDouble::TAU).round();
+        let r = self - k * DoubleDouble::TAU;
+        let r_square = r.square();
+        let mut sin = r;
+        let mut cos = DoubleDouble::ONE;
+        let mut sin_term = r;
+        let mut cos_term = DoubleDouble::ONE;
+        for n in 1..40 {
+            let n = n as f64;
+            sin_term = -sin_term * r_square / DoubleDouble::from((2.0 * n) * (2.0 * n + 1.0));
+            cos_term = -cos_term * r_square / DoubleDouble::from((2.0 * n - 1.0) * (2.0 * n));
+            sin = sin + sin_term;
+            cos = cos + cos_term;
+            if sin_term.hi.abs() < 1e-34 && cos_term.hi.abs() < 1e-34 {
+                break;
+            }
+        }
+        (sin, cos)
+    }
+
+    /// Hyperbolic sine and cosine, using the series for small arguments to avoid cancellation
+    pub fn sinh_cosh(self) -> (DoubleDouble, DoubleDouble) {
+        if self.hi.abs() > 0.5 {
+            let exp = self.exp();
+            let inv = DoubleDouble::ONE / exp;
+            return ((exp - inv).ldexp(-1), (exp + inv).ldexp(-1));
+        }
+        let x_square = self.square();
+        let mut sinh = self;
+        let mut term = self;
+        for n in 1..30 {
+            let n = n as f64;
+            term = term * x_square / DoubleDouble::from((2.0 * n) * (2.0 * n + 1.0));
+            sinh = sinh + term;
+            if term.hi.abs() < 1e-34 * sinh.hi.abs() {
+                break;
+            }
+        }
+        let cosh = (DoubleDouble::ONE + sinh.square()).sqrt();
+        (sinh, cosh)
+    }
+
+    /// Two-argument arctangent, refined from the f64 estimate with one Newton step
+    pub fn atan2(y: DoubleDouble, x: DoubleDouble) -> DoubleDouble {
+        let estimate = DoubleDouble::from(y.hi.atan2(x.hi));
+        if !estimate.is_finite() || (x.hi == 0.0 && y.hi == 0.0) {
+            return estimate;
+        }
+        let (sin, cos) = estimate.sin_cos();
+        estimate + (y * cos - x * sin) / (x * cos + y * sin)
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> DoubleDouble {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+}
+
+/// The rounding error of `hi` is computed in i128, where `hi` of up to ±2^64 cannot saturate
+impl From<i64> for DoubleDouble {
+    fn from(value: i64) -> DoubleDouble {
+        let hi = value as f64;
+        let lo = (value as i128 - hi as i128) as f64;
+        DoubleDouble::new(hi, lo)
+    }
+}
+
+impl From<u64> for DoubleDouble {
+    fn from(value: u64) -> DoubleDouble {
+        let hi = value as f64;
+        let lo = (value as i128 - hi as i128) as f64;
+        DoubleDouble::new(hi, lo)
+    }
+}
+
+impl PartialOrd for DoubleDouble {
+    fn partial_cmp(&self, other: &DoubleDouble) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi) {
+            Some(Ordering::Equal) => self.lo.partial_cmp(&other.lo),
+            ordering => ordering,
+        }
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (s, e) = two_sum(self.hi, other.hi);
+        let (t, f) = two_sum(self.lo, other.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let (p, e) = two_prod(self.hi, other.hi);
+        let e = e + (self.hi * other.lo + self.lo * other.hi);
+        let (hi, lo) = quick_two_sum(p, e);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn div(self, other: DoubleDouble) -> DoubleDouble {
+        let q1 = self.hi / other.hi;
+        let r = self - other * DoubleDouble::from(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * DoubleDouble::from(q2);
+        let q3 = r.hi / other.hi;
+        let (hi, lo) = quick_two_sum(q1, q2);
+        DoubleDouble { hi, lo } + DoubleDouble::from(q3)
+    }
+}
+
+/// Writes the value in scientific notation with enough digits to be read back by `FromStr`
+impl fmt::Display for DoubleDouble {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_finite() {
+            return write!(f, "{}", self.hi);
+        }
+        if self.hi == 0.0 {
+            return write!(f, "0");
+        }
+        let sign = if self.hi < 0.0 { "-" } else { "" };
+        let value = self.abs();
+
+        // Scale the value to an integer of DISPLAY_DIGITS digits, then print that integer
+        let mut exponent = value.hi.log10().floor() as i32;
+        let mut digits = value
+            .scale_by_power_of_ten(DISPLAY_DIGITS as i32 - 1 - exponent)
+            .to_string();
+        if digits.len() != DISPLAY_DIGITS {
+            // log10 of the high part can be off by one next to a power of ten
+            exponent += digits.len() as i32 - DISPLAY_DIGITS as i32;
+            digits = value
+                .scale_by_power_of_ten(DISPLAY_DIGITS as i32 - 1 - exponent)
+                .to_string();
+        }
+        if digits.len() > DISPLAY_DIGITS {
+            // Rounding carried into a new digit, e.g. 9.99..9 became 10.00..0
+            digits.truncate(DISPLAY_DIGITS);
+            exponent += 1;
+        }
+        let digits = digits.trim_end_matches('0');
+
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            write!(f, "{sign}{first}e{exponent}")
+        } else {
+            write!(f, "{sign}{first}.{rest}e{exponent}")
+        }
+    }
+}
+
+impl DoubleDouble {
+    /// Multiply by `10^power`, in steps of at most `10^MAX_POWER_OF_TEN_STEP` so that the
+    /// power of ten itself never overflows or underflows: only the result can
+    fn mul_power_of_ten(self, power: i32) -> DoubleDouble {
+        let mut value = self;
+        let mut remaining = power;
+        while remaining != 0 && value.hi != 0.0 && value.is_finite() {
+            let step = remaining.clamp(-MAX_POWER_OF_TEN_STEP, MAX_POWER_OF_TEN_STEP);
+            let scale = DoubleDouble::from(10.0).powi(step.abs());
+            value = if step < 0 {
+                value / scale
+            } else {
+                value * scale
+            };
+            remaining -= step;
+        }
+        value
+    }
+
+    /// Round `self * 10^power` to the nearest integer
+    fn scale_by_power_of_ten(self, power: i32) -> i128 {
+        let rounded = self.mul_power_of_ten(power).round();
+        rounded.hi as i128 + rounded.lo as i128
+    }
+
+    /// Build `mantissa * 10^power` with a single rounding step: 0 when it is too small for an
+    /// f64, infinite when it is too large
+    fn from_decimal(mantissa: i128, power: i32) -> DoubleDouble {
+        if mantissa == 0 {
+            return DoubleDouble::ZERO;
+        }
+        // the mantissa is an integer of at most 35 digits, so past these powers only the limit is
+        // left
+        if power < -MIN_DECIMAL_EXPONENT {
+            return DoubleDouble::ZERO;
+        }
+        if power > MAX_DECIMAL_EXPONENT {
+            return DoubleDouble::from(f64::INFINITY);
+        }
+        let hi = mantissa as f64;
+        let lo = (mantissa - hi as i128) as f64;
+        let value = DoubleDouble::new(hi, lo).mul_power_of_ten(power);
+        if value.is_nan() {
+            // the product overflowed in the error term
+            DoubleDouble::from(f64::INFINITY)
+        } else {
+            value
+        }
+    }
+}
+
+/// Parses a decimal number such as `-0.743643887037158704752191506114774` or `1.5e-20`
+impl FromStr for DoubleDouble {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<DoubleDouble, String> {
+        let text = text.trim();
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => (&unsigned[..index], &unsigned[index + 1..]),
+            None => (unsigned, "0"),
+        };
+        let mut exponent: i32 = exponent
+            .parse()
+            .map_err(|_| format!("invalid exponent in {text:?}"))?;
+
+        // Digits beyond what a double-double can hold are dropped, only their magnitude is kept
+        let mut value: i128 = 0;
+        let mut significant_digits = 0;
+        let mut seen_digit = false;
+        let mut seen_point = false;
+        for character in mantissa.chars() {
+            match character {
+                '0'..='9' => {
+                    seen_digit = true;
+                    if value == 0 && character == '0' {
+                        if seen_point {
+                            exponent = exponent.saturating_sub(1);
+                        }
+                    } else if significant_digits < DISPLAY_DIGITS + 4 {
+                        value = value * 10 + (character as u8 - b'0') as i128;
+                        significant_digits += 1;
+                        if seen_point {
+                            exponent = exponent.saturating_sub(1);
+                        }
+                    } else if !seen_point {
+                        exponent = exponent.saturating_add(1);
+                    }
+                }
+                '.' if !seen_point => seen_point = true,
+                _ => return Err(format!("invalid number {text:?}")),
+            }
+        }
+        if !seen_digit {
+            return Err(format!("invalid number {text:?}"));
+        }
+
+        let value = DoubleDouble::from_decimal(value, exponent);
+        if !value.is_finite() {
+            return Err(format!("{text:?} is too large for a double-double"));
+        }
+        Ok(if negative { -value } else { value })
+    }
+}
+
+/// Serialized as a decimal string so that no digit is lost through JSON
+impl Serialize for DoubleDouble {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct DoubleDoubleVisitor;
+
+impl<'de> Visitor<'de> for DoubleDoubleVisitor {
+    type Value = DoubleDouble;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a decimal string or a number")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<DoubleDouble, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<DoubleDouble, E> {
+        Ok(DoubleDouble::from(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<DoubleDouble, E> {
+        Ok(DoubleDouble::from(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<DoubleDouble, E> {
+        Ok(DoubleDouble::from(value))
+    }
+}
+
+/// Accepts both decimal strings and plain JSON numbers
+impl<'de> Deserialize<'de> for DoubleDouble {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DoubleDouble, D::Error> {
+        deserializer.deserialize_any(DoubleDoubleVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DoubleDouble;
+
+    fn assert_close(left: DoubleDouble, right: DoubleDouble, tolerance: f64) {
+        let difference = (left - right).abs().to_f64();
+        assert!(
+            difference <= tolerance * right.abs().to_f64().max(1.0),
+            "{left} != {right}"
+        );
+    }
+
+    #[test]
+    fn test_addition_keeps_low_bits() {
+        let result = DoubleDouble::from(1.0) + DoubleDouble::from(1e-20);
+        assert_eq!(result.hi, 1.0);
+        assert_eq!(result.lo, 1e-20);
+    }
+
+    #[test]
+    fn test_division() {
+        let third = DoubleDouble::ONE / DoubleDouble::from(3.0);
+        assert_close(third * DoubleDouble::from(3.0), DoubleDouble::ONE, 1e-31);
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let root = DoubleDouble::from(2.0).sqrt();
+        assert_close(root.square(), DoubleDouble::from(2.0), 1e-31);
+    }
+
+    #[test]
+    fn test_exp() {
+        let e = DoubleDouble::ONE.exp();
+        assert_close(
+            e,
+            "2.718281828459045235360287471352662".parse().unwrap(),
+            1e-31,
+        );
+    }
+
+    #[test]
+    fn test_sin_cos() {
+        let (sin, cos) = DoubleDouble::ONE.sin_cos();
+        assert_close(sin.square() + cos.square(), DoubleDouble::ONE, 1e-31);
+        assert_close(
+            sin,
+            "0.8414709848078965066525023216302990".parse().unwrap(),
+            1e-31,
+        );
+    }
+
+    #[test]
+    fn test_integers_at_the_limits() {
+        let two_pow_63 = DoubleDouble::from(2f64.powi(63));
+        assert_eq!(
+            DoubleDouble::from(i64::MAX) - two_pow_63,
+            DoubleDouble::from(-1.0)
+        );
+        assert_eq!(DoubleDouble::from(i64::MIN), -two_pow_63);
+        assert_eq!(
+            DoubleDouble::from(u64::MAX) - two_pow_63 * DoubleDouble::from(2.0),
+            DoubleDouble::from(-1.0)
+        );
+        assert_eq!(
+            DoubleDouble::from(1u64 << 53),
+            DoubleDouble::from(2f64.powi(53))
+        );
+
+        // positive and negative integers above 2^53 keep every digit
+        let positive: DoubleDouble = serde_json::from_str("9007199254740993").unwrap();
+        let negative: DoubleDouble = serde_json::from_str("-9007199254740993").unwrap();
+        assert_eq!(positive, DoubleDouble::from(9007199254740993i64));
+        assert_eq!((positive.hi, positive.lo), (2f64.powi(53), 1.0));
+        assert_eq!(negative, -positive);
+        let max: DoubleDouble = serde_json::from_str(&u64::MAX.to_string()).unwrap();
+        assert_eq!(max, DoubleDouble::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_parse() {
+        let value: DoubleDouble = "-0.743643887037158704752191506114774".parse().unwrap();
+        assert_eq!(value.hi, -0.7436438870371587);
+        assert!(value.lo != 0.0);
+        assert!("1.2.3".parse::<DoubleDouble>().is_err());
+        assert!("".parse::<DoubleDouble>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let text = "-7.436438870371587047521915061148e-1";
+        let value: DoubleDouble = text.parse().unwrap();
+        assert_eq!(value.to_string(), text);
+        let reparsed: DoubleDouble = value.to_string().parse().unwrap();
+        assert_close(reparsed, value, 1e-30);
+        assert_eq!(reparsed.to_string(), text);
+    }
+
+    #[test]
+    fn test_serde() {
+        let value: DoubleDouble = "0.131825904205311970493132056385139".parse().unwrap();
+        let serialized = serde_json::to_string(&value).unwrap();
+        let deserialized: DoubleDouble = serde_json::from_str(&serialized).unwrap();
+        assert_close(deserialized, value, 1e-30);
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), serialized);
+        let from_number: DoubleDouble = serde_json::from_str("0.5").unwrap();
+        assert_eq!(from_number, DoubleDouble::from(0.5));
+    }
+
+    #[test]
+    fn test_display_extreme_exponents() {
+        for text in ["1.5e-300", "-2.25e-310", "1e-320", "1.7e308", "3e-250"] {
+            let value: DoubleDouble = text.parse().unwrap();
+            let displayed = value.to_string();
+            assert!(!displayed.contains("inf") && !displayed.contains("NaN"));
+            let reparsed: DoubleDouble = displayed.parse().unwrap();
+            assert_eq!(reparsed.hi, value.hi, "{text} displayed as {displayed}");
+        }
+        for text in ["1.5e-250", "1.25e-290", "-7.5e300"] {
+            let value: DoubleDouble = text.parse().unwrap();
+            assert_eq!(value.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_parse_extreme_exponents() {
+        assert_eq!("1e-400".parse::<DoubleDouble>(), Ok(DoubleDouble::ZERO));
+        assert_eq!("-1e-400".parse::<DoubleDouble>().unwrap().to_f64(), 0.0);
+        assert_eq!(
+            "1e-2147483648".parse::<DoubleDouble>(),
+            Ok(DoubleDouble::ZERO)
+        );
+        assert_eq!("1e-320".parse::<DoubleDouble>().unwrap().hi, 1e-320);
+        assert_eq!("1.7e308".parse::<DoubleDouble>().unwrap().hi, 1.7e308);
+        assert!("1e400".parse::<DoubleDouble>().is_err());
+        assert!("1.8e308".parse::<DoubleDouble>().is_err());
+    }
+}
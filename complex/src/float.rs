@@ -0,0 +1,120 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Floating point operations needed by `Complex`, implemented for f32 and f64
+/// so that fractals can pick the precision/speed trade-off.
+pub trait Float:
+    Copy
+    + PartialOrd
+    + Debug
+    + Display
+    + FromStr
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const TWO: Self;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn powf(self, exponent: Self) -> Self;
+    fn copysign(self, sign: Self) -> Self;
+    fn is_nan(self) -> bool;
+    fn is_infinite(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($type:ty) => {
+        impl Float for $type {
+            const ZERO: $type = 0.0;
+            const ONE: $type = 1.0;
+            const TWO: $type = 2.0;
+
+            fn from_f64(value: f64) -> $type {
+                value as $type
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn abs(self) -> $type {
+                <$type>::abs(self)
+            }
+
+            fn sqrt(self) -> $type {
+                <$type>::sqrt(self)
+            }
+
+            fn hypot(self, other: $type) -> $type {
+                <$type>::hypot(self, other)
+            }
+
+            fn sin(self) -> $type {
+                <$type>::sin(self)
+            }
+
+            fn cos(self) -> $type {
+                <$type>::cos(self)
+            }
+
+            fn sinh(self) -> $type {
+                <$type>::sinh(self)
+            }
+
+            fn cosh(self) -> $type {
+                <$type>::cosh(self)
+            }
+
+            fn atan2(self, other: $type) -> $type {
+                <$type>::atan2(self, other)
+            }
+
+            fn exp(self) -> $type {
+                <$type>::exp(self)
+            }
+
+            fn ln(self) -> $type {
+                <$type>::ln(self)
+            }
+
+            fn powf(self, exponent: $type) -> $type {
+                <$type>::powf(self, exponent)
+            }
+
+            fn copysign(self, sign: $type) -> $type {
+                <$type>::copysign(self, sign)
+            }
+
+            fn is_nan(self) -> bool {
+                <$type>::is_nan(self)
+            }
+
+            fn is_infinite(self) -> bool {
+                <$type>::is_infinite(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
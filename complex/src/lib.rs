@@ -1 +1,5 @@
 pub mod complex;
+pub mod complex_dd;
+pub mod double_double;
+pub mod float;
+pub mod polynomial;
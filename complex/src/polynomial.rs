@@ -0,0 +1,244 @@
+use crate::complex::Complex;
+use crate::float::Float;
+use serde::{Deserialize, Serialize};
+
+/// Polynomial with complex coefficients, stored from the constant term up:
+/// `coefficients[k]` is the coefficient of `z^k`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Polynomial<T = f64> {
+    pub coefficients: Vec<Complex<T>>,
+}
+
+impl<T: Float> Polynomial<T> {
+    pub fn new(coefficients: Vec<Complex<T>>) -> Polynomial<T> {
+        Polynomial { coefficients }
+    }
+
+    /// Build the monic polynomial `(z - r0)(z - r1)...` having the given roots
+    pub fn from_roots(roots: &[Complex<T>]) -> Polynomial<T> {
+        let mut coefficients = vec![Complex::new(T::ONE, T::ZERO)];
+        for &root in roots {
+            let mut next = vec![Complex::new(T::ZERO, T::ZERO); coefficients.len() + 1];
+            for (k, &coefficient) in coefficients.iter().enumerate() {
+                next[k + 1] += coefficient;
+                next[k] -= coefficient * root;
+            }
+            coefficients = next;
+        }
+        Polynomial { coefficients }
+    }
+
+    /// Degree of the polynomial, ignoring null leading coefficients
+    pub fn degree(&self) -> usize {
+        self.coefficients
+            .iter()
+            .rposition(|coefficient| coefficient.re != T::ZERO || coefficient.im != T::ZERO)
+            .unwrap_or(0)
+    }
+
+    /// Evaluate the polynomial with the Horner scheme
+    pub fn eval(&self, z: Complex<T>) -> Complex<T> {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Complex::new(T::ZERO, T::ZERO), |result, &coefficient| {
+                result * z + coefficient
+            })
+    }
+
+    pub fn derivative(&self) -> Polynomial<T> {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(k, &coefficient)| coefficient * T::from_f64(k as f64))
+            .collect();
+        Polynomial { coefficients }
+    }
+
+    /// Evaluate the polynomial and its derivative together, in a single Horner pass
+    pub fn eval_with_derivative(&self, z: Complex<T>) -> (Complex<T>, Complex<T>) {
+        let zero = Complex::new(T::ZERO, T::ZERO);
+        self.coefficients
+            .iter()
+            .rev()
+            .fold((zero, zero), |(value, derivative), &coefficient| {
+                (value * z + coefficient, derivative * z + value)
+            })
+    }
+
+    /// Find all the roots with the Aberth-Ehrlich method.
+    /// The iterations stop when every root moved by less than `tolerance` times its modulus
+    /// (or than `tolerance` for the roots inside the unit circle), or after `max_iteration`
+    /// iterations.
+    pub fn roots(&self, max_iteration: usize, tolerance: T) -> Vec<Complex<T>> {
+        let degree = self.degree();
+        if degree == 0 {
+            return Vec::new();
+        }
+        let leading = self.coefficients[degree];
+
+        // Start on a circle of the Cauchy bound radius, rotated to avoid symmetric polynomials
+        let radius = self.coefficients[..degree]
+            .iter()
+            .map(|&coefficient| (coefficient / leading).norm())
+            .fold(T::ZERO, |max, value| if value > max { value } else { max })
+            + T::ONE;
+        let mut roots: Vec<Complex<T>> = (0..degree)
+            .map(|k| {
+                let angle = T::from_f64(std::f64::consts::TAU * k as f64 / degree as f64 + 0.4);
+                Complex::from_polar(radius, angle)
+            })
+            .collect();
+
+        for _ in 0..max_iteration {
+            let mut converged = true;
+            for k in 0..degree {
+                let (value, derivative) = self.eval_with_derivative(roots[k]);
+                if value.re == T::ZERO && value.im == T::ZERO {
+                    continue;
+                }
+                let newton = value / derivative;
+                let repulsion = roots
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != k)
+                    .fold(Complex::new(T::ZERO, T::ZERO), |sum, (_, &other)| {
+                        sum + (roots[k] - other).recip()
+                    });
+                let offset = newton / (-(newton * repulsion) + T::ONE);
+                if offset.is_nan() || offset.is_infinite() {
+                    continue;
+                }
+                roots[k] -= offset;
+                let modulus = roots[k].norm();
+                let scale = if modulus > T::ONE { modulus } else { T::ONE };
+                if offset.norm() > tolerance * scale {
+                    converged = false;
+                }
+            }
+            if converged {
+                break;
+            }
+        }
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Polynomial;
+    use crate::complex::Complex;
+
+    /// p(z) = z^3 - 1
+    fn z3_minus_one() -> Polynomial {
+        Polynomial::new(vec![
+            Complex::new(-1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(1.0, 0.0),
+        ])
+    }
+
+    fn assert_contains(roots: &[Complex], expected: Complex) {
+        assert!(
+            roots.iter().any(|root| (*root - expected).norm() < 1e-10),
+            "{expected} not in {roots:?}"
+        );
+    }
+
+    #[test]
+    fn test_eval() {
+        let polynomial = z3_minus_one();
+        let z = Complex::new(1.0, 2.0);
+        assert_eq!(polynomial.eval(z), z.cube().sub_reel(1.0));
+    }
+
+    #[test]
+    fn test_derivative() {
+        let derivative = z3_minus_one().derivative();
+        let z = Complex::new(1.0, 2.0);
+        assert_eq!(derivative.eval(z), z.square() * 3.0);
+        assert_eq!(derivative.degree(), 2);
+    }
+
+    #[test]
+    fn test_eval_with_derivative() {
+        let polynomial = z3_minus_one();
+        let z = Complex::new(-0.5, 0.75);
+        let (value, derivative) = polynomial.eval_with_derivative(z);
+        assert_eq!(value, polynomial.eval(z));
+        assert_eq!(derivative, polynomial.derivative().eval(z));
+    }
+
+    #[test]
+    fn test_degree_ignores_null_leading_coefficients() {
+        let mut polynomial = z3_minus_one();
+        polynomial.coefficients.push(Complex::new(0.0, 0.0));
+        assert_eq!(polynomial.degree(), 3);
+        assert_eq!(Polynomial::<f64>::new(vec![]).degree(), 0);
+    }
+
+    #[test]
+    fn test_roots_of_unity() {
+        let roots = z3_minus_one().roots(100, 1e-14);
+        assert_eq!(roots.len(), 3);
+        for k in 0..3 {
+            let angle = std::f64::consts::TAU * k as f64 / 3.0;
+            assert_contains(&roots, Complex::from_polar(1.0, angle));
+        }
+    }
+
+    #[test]
+    fn test_from_roots() {
+        let expected = [
+            Complex::new(2.0, 0.0),
+            Complex::new(-1.0, 1.0),
+            Complex::new(0.0, -0.5),
+            Complex::new(0.0, 0.0),
+        ];
+        let polynomial = Polynomial::from_roots(&expected);
+        assert_eq!(polynomial.degree(), 4);
+        for root in expected {
+            assert!(polynomial.eval(root).norm() < 1e-12);
+        }
+        let roots = polynomial.roots(200, 1e-14);
+        for root in expected {
+            assert_contains(&roots, root);
+        }
+    }
+
+    #[test]
+    fn test_roots_far_from_origin_with_relative_tolerance() {
+        // z^3 = 3e6 + 0.7i, three roots of modulus about 144
+        let constant = Complex::new(3e6, 0.7);
+        let polynomial = Polynomial::new(vec![
+            -constant,
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(1.0, 0.0),
+        ]);
+        let roots = polynomial.roots(500, f64::EPSILON);
+        assert_eq!(roots.len(), 3);
+        let modulus = constant.norm().powf(1.0 / 3.0);
+        for k in 0..3 {
+            let angle = (constant.arg() + std::f64::consts::TAU * k as f64) / 3.0;
+            let expected = Complex::from_polar(modulus, angle);
+            assert!(
+                roots
+                    .iter()
+                    .any(|root| (*root - expected).norm() < 1e-12 * modulus),
+                "{expected} not in {roots:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let polynomial = z3_minus_one();
+        let serialized = serde_json::to_string(&polynomial).unwrap();
+        let deserialized: Polynomial = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(polynomial, deserialized);
+    }
+}
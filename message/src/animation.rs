@@ -0,0 +1,244 @@
+use std::fs;
+
+use complex::complex_dd::ComplexDD;
+use complex::double_double::DoubleDouble;
+use serde::{Deserialize, Serialize};
+
+use crate::message::{Point, Range};
+use crate::scene::Scene;
+
+/// Speed of the zoom along the animation, applied to the fraction of the animation elapsed.
+/// The zoom is geometric: with `Linear`, every frame is narrower than the previous one by the
+/// same ratio, so that the zoom looks steady.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Starts and ends slowly
+    EaseInOut,
+}
+
+impl Easing {
+    /// Eased fraction of the animation, from 0 at `t` = 0 to 1 at `t` = 1
+    pub fn apply(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Zoom from the range of a scene to a window `zoom_factor` times narrower around
+/// `end_center`, in `frames` images. The centre moves at the pace of the zoom, so that the
+/// end centre goes straight to the middle of the screen instead of drifting off it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Zoom {
+    /// First frame: fractal, resolution, start range and coloring of the whole animation
+    pub scene: Scene,
+    /// Centre of the last frame, as decimal strings to keep deep-zoom digits
+    pub end_center: ComplexDD,
+    /// Width of the first frame divided by the width of the last one
+    pub zoom_factor: f64,
+    pub frames: u32,
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl Zoom {
+    /// Load a zoom from a JSON file and check it
+    pub fn load(path: &str) -> Result<Zoom, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("Cannot read {path}: {err}"))?;
+        let zoom: Zoom =
+            serde_json::from_str(&content).map_err(|err| format!("Invalid zoom {path}: {err}"))?;
+        zoom.validate()
+            .map_err(|err| format!("Invalid zoom {path}: {err}"))?;
+        Ok(zoom)
+    }
+
+    /// Check the scene, that there is a frame and that the zoom factor is positive
+    pub fn validate(&self) -> Result<(), String> {
+        self.scene.validate()?;
+        if self.frames == 0 {
+            return Err("The animation has no frame".to_string());
+        }
+        if !self.zoom_factor.is_finite() || self.zoom_factor <= 0.0 {
+            return Err(format!(
+                "The zoom factor {} is not positive",
+                self.zoom_factor
+            ));
+        }
+        Ok(())
+    }
+
+    /// The scene of a frame, from 0 to `frames - 1`: the range of the first scene around its
+    /// centre, scaled down by the zoom reached at this frame and centred on the point reached
+    pub fn frame(&self, frame: u32) -> Scene {
+        let t = if self.frames > 1 {
+            frame as f64 / (self.frames - 1) as f64
+        } else {
+            0.0
+        };
+        let eased = self.easing.apply(t);
+        let scale = self.zoom_factor.powf(eased);
+        // fraction of the way to the end centre: the distance to it shrinks with the window
+        let progress = if self.zoom_factor == 1.0 {
+            eased
+        } else {
+            (1.0 - 1.0 / scale) / (1.0 - 1.0 / self.zoom_factor)
+        };
+
+        let range = &self.scene.range;
+        let middle = Point {
+            x: (range.min.x + range.max.x) / 2.0,
+            y: (range.min.y + range.max.y) / 2.0,
+        };
+        let start_center = self.scene.center.unwrap_or_default()
+            + ComplexDD::new(DoubleDouble::from(middle.x), DoubleDouble::from(middle.y));
+        let center =
+            start_center + (self.end_center - start_center).mul_reel(DoubleDouble::from(progress));
+        let scaled = |point: &Point| Point {
+            x: (point.x - middle.x) / scale,
+            y: (point.y - middle.y) / scale,
+        };
+
+        Scene {
+            range: Range {
+                min: scaled(&range.min),
+                max: scaled(&range.max),
+            },
+            center: Some(center),
+            ..self.scene.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use complex::complex::Complex;
+    use complex::complex_dd::ComplexDD;
+
+    use super::{Easing, Zoom};
+    use crate::message::Range;
+    use crate::scene::Scene;
+
+    /// A zoom by 1000 from the window [-2.5, 1.5] x [-1, 1] to a point with more digits than
+    /// an f64, in 11 frames
+    fn zoom(easing: Easing) -> Zoom {
+        let scene: Scene = serde_json::from_str(
+            r#"{
+                "fractal": {"Mandelbrot": {}},
+                "max_iteration": 64,
+                "resolution": {"nx": 4, "ny": 2},
+                "range": {"min": {"x": -2.5, "y": -1.0}, "max": {"x": 1.5, "y": 1.0}}
+            }"#,
+        )
+        .unwrap();
+        Zoom {
+            scene,
+            end_center: serde_json::from_str(
+                r#"{"re": "-0.74364388703715870475", "im": "0.13182590420531197049"}"#,
+            )
+            .unwrap(),
+            zoom_factor: 1000.0,
+            frames: 11,
+            easing,
+        }
+    }
+
+    fn width(range: &Range) -> f64 {
+        range.max.x - range.min.x
+    }
+
+    /// Position of the end centre in a frame, in widths of the frame from its centre
+    fn end_on_screen(zoom: &Zoom, frame: &Scene) -> Complex {
+        (zoom.end_center - frame.center.unwrap()).to_complex() / width(&frame.range)
+    }
+
+    #[test]
+    fn test_first_and_last_frames() {
+        let zoom = zoom(Easing::Linear);
+        let first = zoom.frame(0);
+        assert_eq!(first.center.unwrap().to_complex(), Complex::new(-0.5, 0.0));
+        assert_eq!(width(&first.range), 4.0);
+        assert_eq!(first.range.min.y, -1.0);
+
+        let last = zoom.frame(10);
+        assert_eq!(last.center.unwrap(), zoom.end_center);
+        assert!((width(&last.range) - 4e-3).abs() < 1e-15);
+        assert!((last.range.max.y - 1e-3).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_zoom_is_geometric_and_end_centre_goes_straight_to_the_middle() {
+        let zoom = zoom(Easing::Linear);
+        let ratio = 1000f64.powf(0.1);
+        let start = end_on_screen(&zoom, &zoom.frame(0));
+        let mut previous_fraction = 1.0;
+        for frame in 1..zoom.frames {
+            let (previous, current) = (zoom.frame(frame - 1), zoom.frame(frame));
+            let shrink = width(&previous.range) / width(&current.range);
+            assert!((shrink - ratio).abs() < 1e-9);
+            // the end centre stays on the line from its first position to the middle, moving
+            // toward the middle
+            let on_screen = end_on_screen(&zoom, &current);
+            let fraction = on_screen.re / start.re;
+            assert!(
+                (on_screen - start * fraction).norm() < 1e-12,
+                "frame {frame}"
+            );
+            assert!(
+                fraction < previous_fraction && fraction >= 0.0,
+                "frame {frame}"
+            );
+            previous_fraction = fraction;
+        }
+        assert!(previous_fraction.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_easing_keeps_the_ends() {
+        for easing in [Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            let zoom = zoom(easing);
+            assert_eq!(zoom.frame(0), self::zoom(Easing::Linear).frame(0));
+            assert_eq!(zoom.frame(10).center.unwrap(), zoom.end_center);
+        }
+        let middle = |easing| width(&zoom(easing).frame(5).range);
+        assert!(middle(Easing::EaseIn) > middle(Easing::Linear));
+        assert!(middle(Easing::EaseOut) < middle(Easing::Linear));
+    }
+
+    #[test]
+    fn test_single_frame_and_no_zoom() {
+        let mut zoom = zoom(Easing::Linear);
+        zoom.frames = 1;
+        assert_eq!(width(&zoom.frame(0).range), 4.0);
+
+        zoom.frames = 3;
+        zoom.zoom_factor = 1.0;
+        let middle = zoom.frame(1);
+        assert_eq!(width(&middle.range), 4.0);
+        let halfway =
+            (zoom.end_center + ComplexDD::from(Complex::new(-0.5, 0.0))).mul_reel(0.5.into());
+        assert!((middle.center.unwrap() - halfway).to_complex().norm() < 1e-15);
+    }
+
+    #[test]
+    fn test_invalid_zooms() {
+        let mut zoom = zoom(Easing::Linear);
+        zoom.frames = 0;
+        assert_eq!(zoom.validate().unwrap_err(), "The animation has no frame");
+        zoom.frames = 2;
+        zoom.zoom_factor = -2.0;
+        assert_eq!(
+            zoom.validate().unwrap_err(),
+            "The zoom factor -2 is not positive"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::env;
+use std::process::exit;
+
+use message::color_map::{ColorSource, Coloring};
+use message::export::export_image;
+use message::frakt::Frakt;
+use message::scene::Scene;
+
+const USAGE: &str = "Usage: recolor <input.frakt> <output.png> [--palette <name|gradient.json>] \
+[--offset <f64>] [--cycle <f64>] [--source Zn|Count|Both] [--equalize] [--16bit]";
+
+/// Print the error and the usage, then exit
+fn usage_error(message: &str) -> ! {
+    println!("{}", message);
+    println!("{}", USAGE);
+    exit(1);
+}
+
+/// Parse the value following a flag
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        _ => usage_error(&format!("Invalid or missing value for {flag}")),
+    }
+}
+
+/// Colour the pixel intensities of a .frakt file into an image, with the coloring of its
+/// scene changed by the flags, or export them raw in the format of the output extension
+fn main() {
+    let mut args = env::args().skip(1);
+    let (input, output) = match (args.next(), args.next()) {
+        (Some(input), Some(output)) => (input, output),
+        _ => usage_error("Missing input or output file"),
+    };
+
+    let frakt = match Frakt::load(&input) {
+        Ok(frakt) => frakt,
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    };
+
+    let mut coloring: Coloring = frakt.scene.coloring();
+    let mut sixteen_bit = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--palette" => coloring.palette = flag_value(&flag, args.next()),
+            "--offset" => coloring.offset = flag_value(&flag, args.next()),
+            "--cycle" => coloring.cycle = flag_value(&flag, args.next()),
+            "--source" => {
+                coloring.source = match args.next().as_deref() {
+                    Some("Zn") => ColorSource::Zn,
+                    Some("Count") => ColorSource::Count,
+                    Some("Both") => ColorSource::Both,
+                    _ => usage_error("Invalid or missing value for --source"),
+                }
+            }
+            "--equalize" => coloring.equalize = true,
+            "--16bit" => sixteen_bit = true,
+            _ => usage_error(&format!("Unknown argument {flag}")),
+        }
+    }
+
+    let color_map = match coloring.color_map() {
+        Ok(color_map) => color_map,
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    };
+    let scene = Scene {
+        coloring: Some(coloring),
+        ..frakt.scene
+    };
+    if let Err(err) = export_image(
+        &output,
+        sixteen_bit,
+        &frakt.pixels,
+        &scene,
+        color_map.as_ref(),
+    ) {
+        println!("{}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,78 @@
+use complex::complex::Complex;
+use rand::Rng;
+
+use crate::message::{Buddhabrot, PixelIntensity, Range, Resolution};
+
+/// Every orbit of the Mandelbrot set escaping to infinity has a `c` in this square
+const SAMPLING_RADIUS: f64 = 2.0;
+
+impl Buddhabrot {
+    /// Calculate a partial Buddhabrot histogram for a given resolution and range.
+    /// `samples` random `c` values are taken in [-2, 2]², and every point of the orbit of
+    /// those escaping after at least `min_iteration` iterations increments the pixel it lands on.
+    /// `count` holds the raw number of hits, so that histograms of several workers can be
+    /// summed, and `zn` the square root of the hits divided by the maximum of the histogram.
+    pub fn calculate_buddhabrot(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        let width = resolution.nx as usize;
+        let height = resolution.ny as usize;
+        let mut hits = vec![0u32; width * height];
+        let mut orbit: Vec<Complex> = Vec::with_capacity(max_iteration as usize);
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..self.samples {
+            let c = Complex::new(
+                rng.gen_range(-SAMPLING_RADIUS..SAMPLING_RADIUS),
+                rng.gen_range(-SAMPLING_RADIUS..SAMPLING_RADIUS),
+            );
+            if Self::is_in_main_bulbs(c) || !Self::escaping_orbit(c, max_iteration, &mut orbit) {
+                continue;
+            }
+            if orbit.len() < self.min_iteration as usize {
+                continue;
+            }
+            for z in &orbit {
+                let x = (z.re - range.min.x) / (range.max.x - range.min.x) * width as f64;
+                let y = (z.im - range.min.y) / (range.max.y - range.min.y) * height as f64;
+                if x >= 0.0 && y >= 0.0 && (x as usize) < width && (y as usize) < height {
+                    hits[y as usize * width + x as usize] += 1;
+                }
+            }
+        }
+
+        let max_hits = hits.iter().copied().max().unwrap_or(0).max(1) as f32;
+        hits.iter()
+            .map(|&hit| PixelIntensity {
+                zn: (hit as f32 / max_hits).sqrt(),
+                count: hit as f32,
+            })
+            .collect()
+    }
+
+    /// Whether `c` is in the main cardioid or the period-2 bulb, whose orbits never escape
+    fn is_in_main_bulbs(c: Complex) -> bool {
+        let q = (c.re - 0.25).powi(2) + c.im.powi(2);
+        let in_cardioid = q * (q + (c.re - 0.25)) <= 0.25 * c.im.powi(2);
+        let in_bulb = (c.re + 1.0).powi(2) + c.im.powi(2) <= 0.0625;
+        in_cardioid || in_bulb
+    }
+
+    /// Iterate z_{n+1} = z_n^2 + c from 0, keeping the orbit.
+    /// Return whether it escaped before `max_iteration` iterations.
+    fn escaping_orbit(c: Complex, max_iteration: u16, orbit: &mut Vec<Complex>) -> bool {
+        orbit.clear();
+        let mut z = Complex::new(0.0, 0.0);
+        for _ in 0..max_iteration {
+            z = z.square() + c;
+            if z.norm_squared() > 4.0 {
+                return true;
+            }
+            orbit.push(z);
+        }
+        false
+    }
+}
@@ -0,0 +1,35 @@
+use crate::escape_time::EscapeTime;
+use crate::message::{Celtic, OrbitTrap, PixelIntensity, Range, Resolution};
+use crate::plane::PlaneFractal;
+use complex::complex::Complex;
+
+impl Celtic {
+    /// Calculate the Celtic fractal for a given resolution and range,
+    /// in Julia mode when `c` is set.
+    pub fn calculate_celtic(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_plane(self.default_plane(), max_iteration, resolution, range)
+    }
+}
+
+/// z_{n+1} = |Re(z_n^2)| + i Im(z_n^2) + c
+impl EscapeTime for Celtic {
+    type State = ();
+
+    fn julia_c(&self) -> Option<Complex> {
+        self.c
+    }
+
+    fn orbit_trap(&self) -> Option<OrbitTrap> {
+        self.orbit_trap
+    }
+
+    fn iterate(&self, z: Complex, c: Complex, _state: &mut ()) -> Complex {
+        let square = z.square();
+        Complex::new(square.re.abs(), square.im) + c
+    }
+}
@@ -0,0 +1,91 @@
+use complex::complex::Complex;
+
+use crate::formula::{Formula, Variables};
+use crate::message::{CustomFormula, PixelIntensity, Plane, Range, Resolution};
+
+impl CustomFormula {
+    /// Calculate the fractal of the formula for a given resolution and range.
+    /// The expression and `z0` are parsed once, and a parse error is returned instead of the
+    /// pixels. The iteration stops when `|z|` exceeds `bailout` or, if `convergence_epsilon` is
+    /// positive, when two successive terms are closer than it. As for the Magnet fractals:
+    /// * escape: `zn = |z|^2 / bailout^2 / 2` for the last point inside, in [0, 0.5]
+    /// * convergence: `zn = 1 - |z_{n+1} - z_n| / epsilon / 2`, in ]0.5, 1]
+    /// * neither after `max_iteration` iterations: `zn = 0` and `count = 1`, which no other pixel
+    ///   has
+    ///
+    /// Without a plane, `c` is the pixel and `z0` is evaluated for every pixel;
+    /// a plane replaces `z0` and `c` as for the other fractals.
+    pub fn calculate_custom_formula(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+        plane: Option<Plane>,
+    ) -> Result<Vec<PixelIntensity>, String> {
+        let (formula, z0) = self.parse()?;
+        let bailout_square = self.bailout * self.bailout;
+
+        let width = resolution.nx;
+        let height = resolution.ny;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        let mut stack = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let x_frac = x as f64 / width as f64 * (range.max.x - range.min.x) + range.min.x;
+                let y_frac = y as f64 / height as f64 * (range.max.y - range.min.y) + range.min.y;
+                let pixel = Complex::new(x_frac, y_frac);
+                let zero = Complex::new(0.0, 0.0);
+                let mut variables = Variables {
+                    z: zero,
+                    previous_z: zero,
+                    c: pixel,
+                    pixel,
+                };
+                match plane {
+                    Some(plane) => (variables.z, variables.c) = plane.start(pixel),
+                    None => variables.z = z0.eval(&variables, &mut stack),
+                }
+
+                let mut zn = 0.0;
+                let mut count = max_iteration;
+                for iteration in 0..max_iteration {
+                    let next = formula.eval(&variables, &mut stack);
+                    if next.is_nan() || next.is_infinite() || next.norm_squared() > bailout_square {
+                        zn = variables.z.norm_squared() / bailout_square / 2.0;
+                        count = iteration;
+                        break;
+                    }
+                    let step = (next - variables.z).norm();
+                    if step < self.convergence_epsilon {
+                        zn = 1.0 - step / self.convergence_epsilon / 2.0;
+                        count = iteration;
+                        break;
+                    }
+                    variables.previous_z = variables.z;
+                    variables.z = next;
+                }
+                pixels.push(PixelIntensity {
+                    zn: zn as f32,
+                    count: (count as f64 / max_iteration as f64) as f32,
+                });
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// Parse the expression and `z0`, and check the bailout
+    pub(crate) fn parse(&self) -> Result<(Formula, Formula), String> {
+        let formula = Formula::parse(&self.expression)
+            .map_err(|err| format!("Invalid expression \"{}\": {err}", self.expression))?;
+        let z0 =
+            Formula::parse(&self.z0).map_err(|err| format!("Invalid z0 \"{}\": {err}", self.z0))?;
+        if self.bailout.is_nan() || self.bailout <= 0.0 {
+            return Err(format!(
+                "Invalid bailout {}: it must be positive",
+                self.bailout
+            ));
+        }
+        Ok((formula, z0))
+    }
+}
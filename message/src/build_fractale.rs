@@ -1,175 +1,261 @@
-use crate::drawing_image::create_image;
+use crate::drawing_image::{create_image_with_mode, ColorMode};
 use crate::message::{
-    FractalDescriptor, FragmentResult, FragmentTask, IteratedSinZ, JuliaDescriptor, Mandelbrot,
-    NewtonRaphsonZ3, NewtonRaphsonZ4, NovaNewtonRaphsonZ3, NovaNewtonRaphsonZ4, PixelData,
-    PixelIntensity, U8Data,
+    Buddhabrot, BurningShip, Celtic, CustomFormula, FractalDescriptor, FragmentResult,
+    FragmentTask, IteratedSinZ, JuliaDescriptor, Lyapunov, MagnetI, MagnetII, Mandelbrot,
+    Multibrot, Multijulia, NewtonPolynomial, NewtonRaphsonZ3, NewtonRaphsonZ4, NovaNewtonRaphsonZ3,
+    NovaNewtonRaphsonZ4, Phoenix, PixelData, PixelIntensity, Supersampling, Tricorn, U8Data,
 };
+use crate::perturbation::Perturbation;
+use crate::plane::PlaneFractal;
+use crate::scene::Scene;
 use image::EncodableLayout;
 
 /// Implementation of the FragmentTask struct. Is just a wrapper around the FractalDescriptor
 /// and the parameters to calculate the fractal. Is Builder pattern.
 impl FragmentTask {
     /// Calculate the fractal based on the fractal descriptor and return the result
-    /// as a FragmentResult and a `Vec<u8>` containing the data_id and the result.
-    pub fn calculate_fractal(&self, data_id: Vec<u8>) -> (FragmentResult, Vec<u8>) {
-        let result_vec_u8: (Vec<u8>, u32) = match self.fractal {
-            FractalDescriptor::Julia(julia) => {
-                let julia_pixel_intensity = Self::calculate_fractal_julia(self, julia);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &julia_pixel_intensity,
-                    "julia.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        julia_pixel_intensity.clone(),
-                    ),
-                    julia_pixel_intensity.len() as u32,
-                )
-            }
+    /// as a FragmentResult and a `Vec<u8>` containing the data_id and the result,
+    /// or the reason why the fractal cannot be calculated.
+    pub fn calculate_fractal(&self, data_id: Vec<u8>) -> Result<(FragmentResult, Vec<u8>), String> {
+        let pixel_intensity = self.calculate_pixels()?;
+        create_image_with_mode(
+            self.resolution.nx as u32,
+            self.resolution.ny as u32,
+            &pixel_intensity,
+            self.fractal.image_filename().to_string(),
+            self.fractal.color_mode(),
+            &Scene::from_task(self),
+        );
+        let count = pixel_intensity.len() as u32;
+        let data = Self::transform_vec_pixel_intensity_to_vec_u8(self, pixel_intensity);
+        Ok((
+            FragmentResult {
+                id: U8Data {
+                    offset: 0,
+                    count: self.id.count,
+                },
+                resolution: self.resolution.clone(),
+                range: self.range.clone(),
+                pixels: PixelData::create_pixel_data(count, Some(self.id.count)),
+            },
+            [data_id.as_bytes(), data.as_bytes()].concat(),
+        ))
+    }
+
+    /// Calculate the pixel intensities of the fractal, without saving an image,
+    /// or the reason why the fractal cannot be calculated.
+    pub fn calculate_pixels(&self) -> Result<Vec<PixelIntensity>, String> {
+        if self.plane.is_some() && !self.fractal.has_planes() {
+            return Err(format!(
+                "{:?} cannot be drawn in another plane",
+                self.fractal
+            ));
+        }
+        if let Some(supersampling) = self.supersampling {
+            self.fractal.validate_supersampling(supersampling)?;
+        }
+        if let (Some(center), false) = (self.center, self.fractal.has_perturbation()) {
+            // the offset from the centre is only kept by perturbation, the others round it
+            let absolute = FragmentTask {
+                range: Scene::absolute_range_around(center, &self.range),
+                center: None,
+                ..self.clone()
+            };
+            return absolute.calculate_pixels();
+        }
+        Ok(match &self.fractal {
+            FractalDescriptor::Julia(julia) => self.calculate_fractal_julia(*julia),
             FractalDescriptor::Mandelbrot(mandelbrot) => {
-                let mandelbrot_pixel_intensity =
-                    Self::calculate_fractal_mandelbrot(self, mandelbrot);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &mandelbrot_pixel_intensity,
-                    "mandelbrot.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        mandelbrot_pixel_intensity.clone(),
-                    ),
-                    mandelbrot_pixel_intensity.len() as u32,
-                )
+                self.calculate_fractal_mandelbrot(*mandelbrot)
+            }
+            FractalDescriptor::Multibrot(multibrot) => self.calculate_fractal_multibrot(*multibrot),
+            FractalDescriptor::Multijulia(multijulia) => {
+                self.calculate_fractal_multijulia(*multijulia)
+            }
+            FractalDescriptor::Phoenix(phoenix) => self.calculate_fractal_phoenix(*phoenix),
+            FractalDescriptor::Lyapunov(lyapunov) => self.calculate_fractal_lyapunov(lyapunov),
+            FractalDescriptor::Buddhabrot(buddhabrot) => {
+                self.calculate_fractal_buddhabrot(*buddhabrot)
             }
-            FractalDescriptor::IteratedSinZ(sin_z) => {
-                let sin_z_pixel_intensity = Self::calculate_fractal_iterated_sin_z(self, sin_z);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &sin_z_pixel_intensity,
-                    "sinZ.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        sin_z_pixel_intensity.clone(),
-                    ),
-                    sin_z_pixel_intensity.len() as u32,
-                )
+            FractalDescriptor::MagnetI(magnet_i) => self.calculate_fractal_magnet_i(*magnet_i),
+            FractalDescriptor::MagnetII(magnet_ii) => self.calculate_fractal_magnet_ii(*magnet_ii),
+            FractalDescriptor::CustomFormula(custom_formula) => {
+                self.calculate_fractal_custom_formula(custom_formula)?
             }
+            FractalDescriptor::BurningShip(burning_ship) => {
+                self.calculate_fractal_burning_ship(*burning_ship)
+            }
+            FractalDescriptor::Tricorn(tricorn) => self.calculate_fractal_tricorn(*tricorn),
+            FractalDescriptor::Celtic(celtic) => self.calculate_fractal_celtic(*celtic),
+            FractalDescriptor::IteratedSinZ(sin_z) => self.calculate_fractal_iterated_sin_z(*sin_z),
             FractalDescriptor::NewtonRaphsonZ3(newton_raphson_z3) => {
-                let newton_raphson_z3_pixel_intensity =
-                    Self::calculate_fractal_newton_raphson_z3(self, newton_raphson_z3);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &newton_raphson_z3_pixel_intensity,
-                    "newtonZ3.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        newton_raphson_z3_pixel_intensity.clone(),
-                    ),
-                    newton_raphson_z3_pixel_intensity.len() as u32,
-                )
+                self.calculate_fractal_newton_raphson_z3(*newton_raphson_z3)
             }
             FractalDescriptor::NewtonRaphsonZ4(newton_raphson_z4) => {
-                let newton_raphson_z4_pixel_intensity =
-                    Self::calculate_fractal_newton_raphson_z4(self, newton_raphson_z4);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &newton_raphson_z4_pixel_intensity,
-                    "newtonZ4.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        newton_raphson_z4_pixel_intensity.clone(),
-                    ),
-                    newton_raphson_z4_pixel_intensity.len() as u32,
-                )
+                self.calculate_fractal_newton_raphson_z4(*newton_raphson_z4)
+            }
+            FractalDescriptor::NewtonPolynomial(newton_polynomial) => {
+                self.calculate_fractal_newton_polynomial(newton_polynomial)
             }
             FractalDescriptor::NovaNewtonRaphsonZ3(nova_newton_raphson_z3) => {
-                let nova_newton_raphson_z3_pixel_intensity =
-                    Self::calculate_fractal_nova_newton_raphson_z3(self, nova_newton_raphson_z3);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &nova_newton_raphson_z3_pixel_intensity,
-                    "novaNewtonZ3.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        nova_newton_raphson_z3_pixel_intensity.clone(),
-                    ),
-                    nova_newton_raphson_z3_pixel_intensity.len() as u32,
-                )
+                self.calculate_fractal_nova_newton_raphson_z3(*nova_newton_raphson_z3)
             }
             FractalDescriptor::NovaNewtonRaphsonZ4(nova_newton_raphson_z4) => {
-                let nova_newton_raphson_z4_pixel_intensity =
-                    Self::calculate_fractal_nova_newton_raphson_z4(self, nova_newton_raphson_z4);
-                create_image(
-                    self.resolution.nx.clone() as u32,
-                    self.resolution.ny.clone() as u32,
-                    &nova_newton_raphson_z4_pixel_intensity,
-                    "novaNewtonZ4.png".to_string(),
-                );
-                (
-                    Self::transform_vec_pixel_intensity_to_vec_u8(
-                        self,
-                        nova_newton_raphson_z4_pixel_intensity.clone(),
-                    ),
-                    nova_newton_raphson_z4_pixel_intensity.len() as u32,
-                )
+                self.calculate_fractal_nova_newton_raphson_z4(*nova_newton_raphson_z4)
             }
-        };
-        return (
-            FragmentResult {
-                id: U8Data {
-                    offset: 0,
-                    count: self.id.count,
-                },
-                resolution: self.resolution.clone(),
-                range: self.range.clone(),
-                pixels: PixelData::create_pixel_data(result_vec_u8.1.clone(), Some(self.id.count)),
-            },
-            [data_id.as_bytes(), result_vec_u8.0.as_bytes()].concat(),
-        );
+        })
     }
 
     /// Calculate the fractal based on the fractal descriptor and return the result for julia
     fn calculate_fractal_julia(&self, julia_descriptor: JuliaDescriptor) -> Vec<PixelIntensity> {
-        return julia_descriptor.calculate_fractal_julia(
+        self.calculate_perturbed(&julia_descriptor, || {
+            julia_descriptor.calculate_fractal_julia(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for sin_z
+    fn calculate_fractal_iterated_sin_z(&self, sin_z: IteratedSinZ) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&sin_z, || {
+            sin_z.calculate_fractal_iterated_sin_z(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for mandelbrot
+    fn calculate_fractal_mandelbrot(&self, mandelbrot: Mandelbrot) -> Vec<PixelIntensity> {
+        self.calculate_perturbed(&mandelbrot, || {
+            mandelbrot.calculate_mandelbrot(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for multibrot
+    fn calculate_fractal_multibrot(&self, multibrot: Multibrot) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&multibrot, || {
+            multibrot.calculate_multibrot(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for multijulia
+    fn calculate_fractal_multijulia(&self, multijulia: Multijulia) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&multijulia, || {
+            multijulia.calculate_multijulia(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for phoenix
+    fn calculate_fractal_phoenix(&self, phoenix: Phoenix) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&phoenix, || {
+            phoenix.calculate_phoenix(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for lyapunov
+    fn calculate_fractal_lyapunov(&self, lyapunov: &Lyapunov) -> Vec<PixelIntensity> {
+        lyapunov.calculate_lyapunov(
             self.max_iteration,
             self.resolution.clone(),
             self.range.clone(),
-        );
+        )
     }
 
-    /// Calculate the fractal based on the fractal descriptor and return the result for sin_z
-    fn calculate_fractal_iterated_sin_z(&self, sin_z: IteratedSinZ) -> Vec<PixelIntensity> {
-        return sin_z.calculate_fractal_iterated_sin_z(
+    /// Calculate the fractal based on the fractal descriptor and return the result for buddhabrot
+    fn calculate_fractal_buddhabrot(&self, buddhabrot: Buddhabrot) -> Vec<PixelIntensity> {
+        buddhabrot.calculate_buddhabrot(
             self.max_iteration,
             self.resolution.clone(),
             self.range.clone(),
-        );
+        )
     }
 
-    /// Calculate the fractal based on the fractal descriptor and return the result for mandelbrot
-    fn calculate_fractal_mandelbrot(&self, mandelbrot: Mandelbrot) -> Vec<PixelIntensity> {
-        return mandelbrot.calculate_mandelbrot(
+    /// Calculate the fractal based on the fractal descriptor and return the result for magnet_i
+    fn calculate_fractal_magnet_i(&self, magnet_i: MagnetI) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&magnet_i, || {
+            magnet_i.calculate_magnet_i(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for magnet_ii
+    fn calculate_fractal_magnet_ii(&self, magnet_ii: MagnetII) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&magnet_ii, || {
+            magnet_ii.calculate_magnet_ii(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for custom_formula
+    fn calculate_fractal_custom_formula(
+        &self,
+        custom_formula: &CustomFormula,
+    ) -> Result<Vec<PixelIntensity>, String> {
+        custom_formula.calculate_custom_formula(
             self.max_iteration,
             self.resolution.clone(),
             self.range.clone(),
-        );
+            self.plane,
+        )
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for burning_ship
+    fn calculate_fractal_burning_ship(&self, burning_ship: BurningShip) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&burning_ship, || {
+            burning_ship.calculate_burning_ship(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for tricorn
+    fn calculate_fractal_tricorn(&self, tricorn: Tricorn) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&tricorn, || {
+            tricorn.calculate_tricorn(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for celtic
+    fn calculate_fractal_celtic(&self, celtic: Celtic) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&celtic, || {
+            celtic.calculate_celtic(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
     }
 
     /// Calculate the fractal based on the fractal descriptor and return the result for newton_raphson_z3
@@ -177,34 +263,48 @@ impl FragmentTask {
         &self,
         newton_raphson_z: NewtonRaphsonZ3,
     ) -> Vec<PixelIntensity> {
-        return newton_raphson_z.calculate_fractal_newton_raphson_z3(
+        newton_raphson_z.calculate_fractal_newton_raphson_z3(
             self.max_iteration,
             self.resolution.clone(),
             self.range.clone(),
-        );
+        )
     }
 
     fn calculate_fractal_newton_raphson_z4(
         &self,
         newton_raphson_z: NewtonRaphsonZ4,
     ) -> Vec<PixelIntensity> {
-        return newton_raphson_z.calculate_fractal_newton_raphson_z4(
+        newton_raphson_z.calculate_fractal_newton_raphson_z4(
             self.max_iteration,
             self.resolution.clone(),
             self.range.clone(),
-        );
+        )
     }
 
-    /// Calculate the fractal based on the fractal descriptor and return the result for nova_newton_raphson_z3
-    fn calculate_fractal_nova_newton_raphson_z3(
+    /// Calculate the fractal based on the fractal descriptor and return the result for newton_polynomial
+    fn calculate_fractal_newton_polynomial(
         &self,
-        nova_newton_raphson_z: NovaNewtonRaphsonZ3,
+        newton_polynomial: &NewtonPolynomial,
     ) -> Vec<PixelIntensity> {
-        return nova_newton_raphson_z.calculate_fractal_nova_newton_raphson_z3(
+        newton_polynomial.calculate_fractal_newton_polynomial(
             self.max_iteration,
             self.resolution.clone(),
             self.range.clone(),
-        );
+        )
+    }
+
+    /// Calculate the fractal based on the fractal descriptor and return the result for nova_newton_raphson_z3
+    fn calculate_fractal_nova_newton_raphson_z3(
+        &self,
+        nova_newton_raphson_z: NovaNewtonRaphsonZ3,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_task_plane(&nova_newton_raphson_z, || {
+            nova_newton_raphson_z.calculate_fractal_nova_newton_raphson_z3(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
     }
 
     /// Calculate the fractal based on the fractal descriptor and return the result for nova_newton_raphson_z4
@@ -212,11 +312,58 @@ impl FragmentTask {
         &self,
         nova_newton_raphson_z: NovaNewtonRaphsonZ4,
     ) -> Vec<PixelIntensity> {
-        return nova_newton_raphson_z.calculate_fractal_nova_newton_raphson_z4(
-            self.max_iteration,
-            self.resolution.clone(),
-            self.range.clone(),
-        );
+        self.calculate_in_task_plane(&nova_newton_raphson_z, || {
+            nova_newton_raphson_z.calculate_fractal_nova_newton_raphson_z4(
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            )
+        })
+    }
+
+    /// Calculate a fractal iterating z^2 + c by perturbation around the centre of the task when
+    /// it has one, or as any other fractal
+    fn calculate_perturbed(
+        &self,
+        fractal: &impl Perturbation,
+        calculate: impl FnOnce() -> Vec<PixelIntensity>,
+    ) -> Vec<PixelIntensity> {
+        match self.center {
+            Some(center) => fractal.calculate_deep(
+                self.plane.unwrap_or_else(|| fractal.default_plane()),
+                center,
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+                self.supersampling,
+            ),
+            None => self.calculate_in_task_plane(fractal, calculate),
+        }
+    }
+
+    /// Calculate the fractal in the plane of the task, supersampled when the task asks for it,
+    /// or with its own calculation when the task chooses neither
+    fn calculate_in_task_plane(
+        &self,
+        fractal: &impl PlaneFractal,
+        calculate: impl FnOnce() -> Vec<PixelIntensity>,
+    ) -> Vec<PixelIntensity> {
+        match (self.plane, self.supersampling) {
+            (plane, Some(supersampling)) => fractal.calculate_supersampled(
+                plane.unwrap_or_else(|| fractal.default_plane()),
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+                supersampling,
+            ),
+            (Some(plane), None) => fractal.calculate_in_plane(
+                plane,
+                self.max_iteration,
+                self.resolution.clone(),
+                self.range.clone(),
+            ),
+            (None, None) => calculate(),
+        }
     }
 
     /// Transform a Vec<PixelIntensity> to a Vec<u8>
@@ -224,7 +371,7 @@ impl FragmentTask {
         &self,
         vec_pixel_intensity: Vec<PixelIntensity>,
     ) -> Vec<u8> {
-        return vec_pixel_intensity
+        vec_pixel_intensity
             .iter()
             .flat_map(|pixel_intensity| {
                 let zn = pixel_intensity.zn;
@@ -241,16 +388,188 @@ impl FragmentTask {
                 vec_u8.extend_from_slice(&result_count);
                 vec_u8
             })
-            .collect();
+            .collect()
     }
 }
 
 /// PixelData is a struct that contains the offset and the count of pixels.
 impl PixelData {
     pub(crate) fn create_pixel_data(pixels: u32, offset: Option<u32>) -> PixelData {
-        return PixelData {
-            offset: offset.unwrap_or_else(|| 0),
+        PixelData {
+            offset: offset.unwrap_or(0),
             count: pixels,
-        };
+        }
+    }
+}
+
+impl FractalDescriptor {
+    /// Whether the fragments of this fractal are partial histograms covering the whole scene,
+    /// to be summed, instead of tiles of the image
+    pub fn is_histogram(&self) -> bool {
+        matches!(self, FractalDescriptor::Buddhabrot(_))
+    }
+
+    /// How the pixels are coloured: the boundary line art when `zn` is a distance estimate,
+    /// the basins of the roots for the Newton fractals, the density of the hits for the
+    /// histogram fractals
+    pub fn color_mode(&self) -> ColorMode {
+        match self {
+            FractalDescriptor::Mandelbrot(Mandelbrot {
+                distance_estimation: true,
+                ..
+            })
+            | FractalDescriptor::Julia(JuliaDescriptor {
+                distance_estimation: true,
+                ..
+            }) => ColorMode::Boundary,
+            FractalDescriptor::NewtonRaphsonZ3(_)
+            | FractalDescriptor::NewtonRaphsonZ4(_)
+            | FractalDescriptor::NewtonPolynomial(_)
+            | FractalDescriptor::NovaNewtonRaphsonZ3(_)
+            | FractalDescriptor::NovaNewtonRaphsonZ4(_) => ColorMode::Basin,
+            FractalDescriptor::Buddhabrot(_) => ColorMode::Density,
+            _ => ColorMode::Palette,
+        }
+    }
+
+    /// Name of the image file saved by the workers for their fragments
+    fn image_filename(&self) -> &'static str {
+        match self {
+            FractalDescriptor::Julia(_) => "julia.png",
+            FractalDescriptor::Mandelbrot(_) => "mandelbrot.png",
+            FractalDescriptor::Multibrot(_) => "multibrot.png",
+            FractalDescriptor::Multijulia(_) => "multijulia.png",
+            FractalDescriptor::Phoenix(_) => "phoenix.png",
+            FractalDescriptor::Lyapunov(_) => "lyapunov.png",
+            FractalDescriptor::Buddhabrot(_) => "buddhabrot.png",
+            FractalDescriptor::MagnetI(_) => "magnetI.png",
+            FractalDescriptor::MagnetII(_) => "magnetII.png",
+            FractalDescriptor::CustomFormula(_) => "customFormula.png",
+            FractalDescriptor::BurningShip(_) => "burningShip.png",
+            FractalDescriptor::Tricorn(_) => "tricorn.png",
+            FractalDescriptor::Celtic(_) => "celtic.png",
+            FractalDescriptor::IteratedSinZ(_) => "sinZ.png",
+            FractalDescriptor::NewtonRaphsonZ3(_) => "newtonZ3.png",
+            FractalDescriptor::NewtonRaphsonZ4(_) => "newtonZ4.png",
+            FractalDescriptor::NewtonPolynomial(_) => "newtonPolynomial.png",
+            FractalDescriptor::NovaNewtonRaphsonZ3(_) => "novaNewtonZ3.png",
+            FractalDescriptor::NovaNewtonRaphsonZ4(_) => "novaNewtonZ4.png",
+        }
+    }
+
+    /// Whether the fractal iterates z^2 + c and keeps the precision of a high precision centre
+    /// by perturbation
+    pub fn has_perturbation(&self) -> bool {
+        matches!(
+            self,
+            FractalDescriptor::Mandelbrot(_) | FractalDescriptor::Julia(_)
+        )
+    }
+
+    /// Whether the fractal iterates z_{n+1} = f(z_n, c) and can be drawn in both the
+    /// parameter plane and the dynamic plane
+    pub fn has_planes(&self) -> bool {
+        !matches!(
+            self,
+            FractalDescriptor::NewtonRaphsonZ3(_)
+                | FractalDescriptor::NewtonRaphsonZ4(_)
+                | FractalDescriptor::NewtonPolynomial(_)
+                | FractalDescriptor::Lyapunov(_)
+                | FractalDescriptor::Buddhabrot(_)
+        )
+    }
+
+    /// Check that the fractal is drawn pixel by pixel, which supersampling needs, that its `zn`
+    /// can be averaged, and that the number of samples is sensible. The Magnet and Nova
+    /// fractals store in `zn` how the orbit ended (escape or convergence, basin of a root),
+    /// which an average would turn into another ending.
+    pub fn validate_supersampling(&self, supersampling: Supersampling) -> Result<(), String> {
+        let has_categories = matches!(
+            self,
+            FractalDescriptor::CustomFormula(_)
+                | FractalDescriptor::MagnetI(_)
+                | FractalDescriptor::MagnetII(_)
+                | FractalDescriptor::NovaNewtonRaphsonZ3(_)
+                | FractalDescriptor::NovaNewtonRaphsonZ4(_)
+        );
+        if !self.has_planes() || has_categories {
+            return Err(format!("{:?} cannot be supersampled", self));
+        }
+        supersampling.validate()
+    }
+
+    /// Check the parameters that can be wrong in a valid JSON, such as the polynomial of a
+    /// Newton fractal, the sequence of a Lyapunov fractal or the formula of a custom fractal,
+    /// so that a scene is rejected before being sent to the workers
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            FractalDescriptor::CustomFormula(custom_formula) => custom_formula.parse().map(|_| ()),
+            FractalDescriptor::NewtonPolynomial(newton_polynomial) => newton_polynomial.validate(),
+            FractalDescriptor::Lyapunov(lyapunov) => lyapunov.validate(),
+            FractalDescriptor::Mandelbrot(Mandelbrot {
+                distance_estimation: true,
+                orbit_trap: Some(_),
+            })
+            | FractalDescriptor::Julia(JuliaDescriptor {
+                distance_estimation: true,
+                orbit_trap: Some(_),
+                ..
+            }) => Err("distance_estimation and orbit_trap cannot be used together".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::{
+        FractalDescriptor, FragmentTask, MagnetI, Mandelbrot, NovaNewtonRaphsonZ3, Supersampling,
+    };
+
+    /// A 4x3 Tricorn task, around the given centre when it is set
+    fn tricorn_task(range: &str, center: Option<&str>) -> FragmentTask {
+        let center = center.map_or(String::new(), |center| format!(r#", "center": {center}"#));
+        serde_json::from_str(&format!(
+            r#"{{
+                "id": {{"offset": 0, "count": 16}},
+                "max_iteration": 64,
+                "resolution": {{"nx": 4, "ny": 3}},
+                "range": {range},
+                "fractal": {{"Tricorn": {{}}}}{center}
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pixels_around_a_centre_without_perturbation() {
+        let relative = tricorn_task(
+            r#"{"min": {"x": -0.5, "y": -0.5}, "max": {"x": 0.5, "y": 0.5}}"#,
+            Some(r#"{"re": "0.25", "im": "0.5"}"#),
+        );
+        let absolute = tricorn_task(
+            r#"{"min": {"x": -0.25, "y": 0.0}, "max": {"x": 0.75, "y": 1.0}}"#,
+            None,
+        );
+        let pixels = relative.calculate_pixels().unwrap();
+        assert_eq!(pixels.len(), 4 * 3);
+        assert_eq!(pixels, absolute.calculate_pixels().unwrap());
+    }
+
+    #[test]
+    fn test_fractals_of_categories_are_not_supersampled() {
+        let supersampling = Supersampling::Grid { n: 2 };
+        let magnet = FractalDescriptor::MagnetI(MagnetI {
+            divergence_threshold_square: 100.0,
+            convergence_epsilon: 1e-6,
+        });
+        let nova = FractalDescriptor::NovaNewtonRaphsonZ3(NovaNewtonRaphsonZ3 {});
+        let mandelbrot = FractalDescriptor::Mandelbrot(Mandelbrot {
+            distance_estimation: false,
+            orbit_trap: None,
+        });
+        assert!(magnet.validate_supersampling(supersampling).is_err());
+        assert!(nova.validate_supersampling(supersampling).is_err());
+        assert!(mandelbrot.validate_supersampling(supersampling).is_ok());
     }
 }
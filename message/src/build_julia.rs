@@ -1,4 +1,8 @@
-use crate::message::{JuliaDescriptor, PixelIntensity, Range, Resolution};
+use crate::build_mandelbrot::{distance_estimate, DISTANCE_ESTIMATION_THRESHOLD_SQUARE};
+use crate::message::{JuliaDescriptor, PixelIntensity, Plane, Range, Resolution};
+use crate::orbit_trap::TrapDistance;
+use crate::perturbation::Perturbation;
+use crate::plane::PlaneFractal;
 use complex::complex::Complex;
 
 impl JuliaDescriptor {
@@ -11,13 +15,21 @@ impl JuliaDescriptor {
     ) -> Vec<PixelIntensity> {
         let width = resolution.nx;
         let height = resolution.ny;
+        let pixel_size = (range.max.x - range.min.x) / width as f64;
         let mut pixels = Vec::with_capacity(width as usize * height as usize);
 
         for y in 0..height {
             for x in 0..width {
                 let minx = range.min.x + (range.max.x - range.min.x) * (x as f64 / width as f64);
                 let miny = range.min.y + (range.max.y - range.min.y) * (y as f64 / height as f64);
-                let result_all = self.iterate_julia(Complex::new(minx, miny), max_iteration);
+                let result_all = self.iterate_julia(
+                    Complex::new(minx, miny),
+                    Complex::new(1.0, 0.0),
+                    max_iteration,
+                    |z| z.square().add(self.c),
+                    Complex::new(0.0, 0.0),
+                    pixel_size,
+                );
                 let pixel_intensity = PixelIntensity {
                     zn: result_all.0 as f32,
                     count: result_all.1 as f32,
@@ -33,26 +45,103 @@ impl JuliaDescriptor {
     /// Iterate the Julia set
     /// if the point is in the set, return (0.0, 0.0)
     /// if the point is not in the set, return (z.norm_squared() / self.divergence_threshold_square, count as f64 / max_iterations as f64)
-    fn iterate_julia(&self, mut z: Complex, max_iteration: u16) -> (f64, f64) {
-        let mut count = 0;
+    /// `next` gives z_{n+1} = z_n^2 + c from z_n.
+    /// `dz` and `dc` are the derivatives of z_0 and c with respect to the pixel, tracked as
+    /// dz_{n+1} = 2 z_n dz_n + dc when estimating the distance to the set: `zn` is then the
+    /// distance in pixels, 0 inside the set.
+    fn iterate_julia(
+        &self,
+        mut z: Complex,
+        mut dz: Complex,
+        max_iteration: u16,
+        mut next: impl FnMut(Complex) -> Complex,
+        dc: Complex,
+        pixel_size: f64,
+    ) -> (f64, f64) {
         let max_iterations = max_iteration;
+        let threshold = if self.distance_estimation {
+            self.divergence_threshold_square
+                .max(DISTANCE_ESTIMATION_THRESHOLD_SQUARE)
+        } else {
+            self.divergence_threshold_square
+        };
         let mut zn_result: f64 = 0.0;
         let mut normalized_count: f64 = 0.0;
+        let mut trap = TrapDistance::new(self.orbit_trap);
 
-        for _i in 0..max_iterations + 1 {
-            if z.norm_squared() > self.divergence_threshold_square {
-                return (zn_result, normalized_count);
+        for count in 0..=max_iterations {
+            if z.norm_squared() > threshold {
+                if self.distance_estimation {
+                    return (distance_estimate(z, dz) / pixel_size, normalized_count);
+                }
+                return (trap.zn(zn_result), normalized_count);
             }
-            let tmp = z.square().add(self.c);
+            let tmp = next(z);
             if tmp.re.is_nan() || tmp.im.is_nan() || tmp.re.is_infinite() || tmp.im.is_infinite() {
-                return (zn_result, normalized_count);
+                return (trap.zn(zn_result), normalized_count);
             }
+            dz = dz * z * 2.0 + dc;
             z = tmp;
+            trap.visit(z);
             normalized_count = count as f64 / max_iterations as f64;
-            count += 1;
-            zn_result = z.norm_squared() / self.divergence_threshold_square;
+            zn_result = z.norm_squared() / threshold;
         }
 
-        (zn_result, normalized_count)
+        if self.distance_estimation {
+            return (0.0, normalized_count);
+        }
+        (trap.zn(zn_result), normalized_count)
+    }
+}
+
+/// The Julia set is the dynamic plane of z^2 + c
+impl PlaneFractal for JuliaDescriptor {
+    fn default_plane(&self) -> Plane {
+        Plane::Dynamic { c: self.c }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        let (dz, dc) = Plane::Dynamic { c }.derivatives();
+        JuliaDescriptor { c, ..*self }.iterate_julia(
+            z0,
+            dz,
+            max_iteration,
+            |z| z.square().add(c),
+            dc,
+            1.0,
+        )
+    }
+
+    fn calculate_pixel(
+        &self,
+        plane: Plane,
+        pixel: Complex,
+        pixel_size: f64,
+        max_iteration: u16,
+    ) -> (f64, f64) {
+        let (z0, c) = plane.start(pixel);
+        let (dz, dc) = plane.derivatives();
+        JuliaDescriptor { c, ..*self }.iterate_julia(
+            z0,
+            dz,
+            max_iteration,
+            |z| z.square().add(c),
+            dc,
+            pixel_size,
+        )
+    }
+}
+
+impl Perturbation for JuliaDescriptor {
+    fn follow_orbit(
+        &self,
+        plane: Plane,
+        z0: Complex,
+        next: impl FnMut(Complex) -> Complex,
+        pixel_size: f64,
+        max_iteration: u16,
+    ) -> (f64, f64) {
+        let (dz, dc) = plane.derivatives();
+        self.iterate_julia(z0, dz, max_iteration, next, dc, pixel_size)
     }
 }
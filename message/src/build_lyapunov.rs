@@ -0,0 +1,170 @@
+use crate::message::{Lyapunov, PixelIntensity, Range, Resolution};
+
+/// Starting value of the logistic map, next to its critical point 0.5 where the derivative is 0
+/// and would make every exponent -∞ without a warmup
+const X0: f64 = 0.5 + 1e-3;
+
+impl Lyapunov {
+    /// Check that the sequence is made of the rates A and B only, in either case
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sequence.is_empty() {
+            return Err("The sequence of the Lyapunov fractal is empty".to_string());
+        }
+        match self
+            .sequence
+            .chars()
+            .find(|letter| !matches!(letter.to_ascii_uppercase(), 'A' | 'B'))
+        {
+            Some(letter) => Err(format!(
+                "Unexpected '{letter}' in the sequence '{}', only A and B are rates",
+                self.sequence
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Calculate the Lyapunov fractal for a given resolution and range.
+    /// The x axis of the range is the rate `a` and the y axis the rate `b`.
+    /// `zn` holds `(tanh(λ) + 1) / 2` for the Lyapunov exponent λ, so it is below 0.5 for
+    /// stable points and above for chaotic ones; `count` is the number of iterations
+    /// used to average λ divided by `max_iteration`.
+    pub fn calculate_lyapunov(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        let sequence = self.rate_sequence();
+
+        let width = resolution.nx;
+        let height = resolution.ny;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let a = x as f64 / width as f64 * (range.max.x - range.min.x) + range.min.x;
+                let b = y as f64 / height as f64 * (range.max.y - range.min.y) + range.min.y;
+
+                let (exponent, count) = self.exponent(&sequence, a, b, max_iteration);
+                pixels.push(PixelIntensity {
+                    zn: ((exponent.tanh() + 1.0) / 2.0) as f32,
+                    count: (count as f64 / max_iteration as f64) as f32,
+                });
+            }
+        }
+        pixels
+    }
+
+    /// The sequence as booleans, `true` selecting the rate `b`.
+    /// Characters other than A and B, rejected by `validate`, are ignored, and an empty
+    /// sequence means "AB".
+    fn rate_sequence(&self) -> Vec<bool> {
+        let sequence: Vec<bool> = self
+            .sequence
+            .chars()
+            .filter_map(|letter| match letter.to_ascii_uppercase() {
+                'A' => Some(false),
+                'B' => Some(true),
+                _ => None,
+            })
+            .collect();
+        if sequence.is_empty() {
+            vec![false, true]
+        } else {
+            sequence
+        }
+    }
+
+    /// Iterate the logistic map x_{n+1} = r_n x_n (1 - x_n), drop the `warmup` first
+    /// iterations and average ln|r_n (1 - 2 x_n)| over the next ones.
+    /// A superstable orbit landing exactly on 0.5 adds the logarithm of the smallest positive
+    /// f64 instead of -∞, so that the exponent stays finite.
+    /// Return the exponent and the number of iterations averaged; the exponent is infinite
+    /// when the orbit escapes.
+    fn exponent(&self, sequence: &[bool], a: f64, b: f64, max_iteration: u16) -> (f64, u16) {
+        let rate = |n: usize| if sequence[n % sequence.len()] { b } else { a };
+        let mut x = X0;
+
+        for n in 0..self.warmup as usize {
+            x = rate(n) * x * (1.0 - x);
+            if !x.is_finite() {
+                return (f64::INFINITY, 0);
+            }
+        }
+
+        let mut sum = 0.0;
+        for count in 0..max_iteration {
+            let r = rate(self.warmup as usize + count as usize);
+            sum += (r * (1.0 - 2.0 * x)).abs().max(f64::MIN_POSITIVE).ln();
+            x = r * x * (1.0 - x);
+            if !x.is_finite() {
+                return (f64::INFINITY, count);
+            }
+        }
+        if max_iteration == 0 {
+            return (0.0, 0);
+        }
+        (sum / max_iteration as f64, max_iteration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::{Lyapunov, Point, Range, Resolution};
+
+    /// `zn` of the single pixel at the rates `a` and `b`
+    fn lyapunov_zn(sequence: &str, warmup: u16, a: f64, b: f64) -> f32 {
+        let lyapunov = Lyapunov {
+            sequence: sequence.to_string(),
+            warmup,
+        };
+        let range = Range {
+            min: Point { x: a, y: b },
+            max: Point {
+                x: a + 1e-9,
+                y: b + 1e-9,
+            },
+        };
+        lyapunov.calculate_lyapunov(1000, Resolution { nx: 1, ny: 1 }, range)[0].zn
+    }
+
+    #[test]
+    fn test_stable_and_chaotic_rates() {
+        for warmup in [0, 100] {
+            // a fixed point of multiplier -0.5, λ = ln 0.5
+            let stable = lyapunov_zn("AB", warmup, 2.5, 2.5);
+            assert!((stable - ((0.5f64.ln().tanh() + 1.0) / 2.0) as f32).abs() < 1e-2);
+            // the fully chaotic logistic map, λ = ln 2
+            let chaotic = lyapunov_zn("AB", warmup, 4.0, 4.0);
+            assert!(chaotic > 0.6, "{chaotic}");
+        }
+    }
+
+    #[test]
+    fn test_superstable_rate_is_finite() {
+        // 0.5 is a fixed point of the map of rate 2
+        let zn = lyapunov_zn("A", 0, 2.0, 2.0);
+        assert!(zn.is_finite() && zn < 0.5);
+    }
+
+    #[test]
+    fn test_invalid_sequences_are_rejected() {
+        let validate = |sequence: &str| {
+            Lyapunov {
+                sequence: sequence.to_string(),
+                warmup: 0,
+            }
+            .validate()
+        };
+        assert!(validate("AabB").is_ok());
+        assert_eq!(
+            validate("").unwrap_err(),
+            "The sequence of the Lyapunov fractal is empty"
+        );
+        assert_eq!(
+            validate("AXB").unwrap_err(),
+            "Unexpected 'X' in the sequence 'AXB', only A and B are rates"
+        );
+        assert!(validate("XYZ").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use complex::complex::Complex;
+
+use crate::message::{MagnetI, MagnetII, PixelIntensity, Plane, Range, Resolution};
+use crate::plane::PlaneFractal;
+
+impl MagnetI {
+    /// Calculate the Magnet Type I fractal for a given resolution and range.
+    pub fn calculate_magnet_i(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_plane(self.default_plane(), max_iteration, resolution, range)
+    }
+
+    /// z_{n+1} = ((z_n^2 + c - 1) / (2 z_n + c - 2))^2
+    fn iterate(z: Complex, c: Complex) -> Complex {
+        ((z.square() + c - 1.0) / (z * 2.0 + c - 2.0)).square()
+    }
+}
+
+impl PlaneFractal for MagnetI {
+    fn default_plane(&self) -> Plane {
+        Plane::Parameter {
+            z0: Complex::new(0.0, 0.0),
+        }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        magnet_orbit(
+            Self::iterate,
+            self.divergence_threshold_square,
+            self.convergence_epsilon,
+            z0,
+            c,
+            max_iteration,
+        )
+    }
+}
+
+impl MagnetII {
+    /// Calculate the Magnet Type II fractal for a given resolution and range.
+    pub fn calculate_magnet_ii(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_plane(self.default_plane(), max_iteration, resolution, range)
+    }
+
+    /// z_{n+1} = ((z_n^3 + 3 (c - 1) z_n + (c - 1)(c - 2)) /
+    ///            (3 z_n^2 + 3 (c - 2) z_n + (c - 1)(c - 2) + 1))^2
+    fn iterate(z: Complex, c: Complex) -> Complex {
+        let c_minus_1 = c - 1.0;
+        let c_minus_2 = c - 2.0;
+        let product = c_minus_1 * c_minus_2;
+        let numerator = z.cube() + c_minus_1 * z * 3.0 + product;
+        let denominator = z.square() * 3.0 + c_minus_2 * z * 3.0 + product + 1.0;
+        (numerator / denominator).square()
+    }
+}
+
+impl PlaneFractal for MagnetII {
+    fn default_plane(&self) -> Plane {
+        Plane::Parameter {
+            z0: Complex::new(0.0, 0.0),
+        }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        magnet_orbit(
+            Self::iterate,
+            self.divergence_threshold_square,
+            self.convergence_epsilon,
+            z0,
+            c,
+            max_iteration,
+        )
+    }
+}
+
+/// Iterate a Magnet map from `z0` with the parameter `c`.
+/// The iteration stops when `|z_n|^2` exceeds `divergence_threshold_square` or when `z_n` is
+/// closer than `convergence_epsilon` to the fixed point 1, and `zn` tells which one happened:
+/// * escape: `zn = |z|^2 / threshold / 2` for the last point inside the threshold, in [0, 0.5]
+/// * convergence to 1: `zn = 1 - |z_n - 1| / epsilon / 2`, in ]0.5, 1]
+/// * neither after `max_iteration` iterations: `zn = 0` and `count = 1`
+///
+/// `count` is the number of iterations before the one that stopped, divided by
+/// `max_iteration`, so that only the orbits stopped by neither have `count = 1`.
+fn magnet_orbit(
+    iterate: fn(Complex, Complex) -> Complex,
+    divergence_threshold_square: f64,
+    convergence_epsilon: f64,
+    z0: Complex,
+    c: Complex,
+    max_iteration: u16,
+) -> (f64, f64) {
+    let mut z = z0;
+    for iteration in 0..max_iteration {
+        let next = iterate(z, c);
+        if next.is_nan() || next.is_infinite() || next.norm_squared() > divergence_threshold_square
+        {
+            let zn = z.norm_squared() / divergence_threshold_square / 2.0;
+            return (zn, iteration as f64 / max_iteration as f64);
+        }
+        z = next;
+        let distance = (z - 1.0).norm();
+        if distance < convergence_epsilon {
+            let zn = 1.0 - distance / convergence_epsilon / 2.0;
+            return (zn, iteration as f64 / max_iteration as f64);
+        }
+    }
+    (0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use complex::complex::Complex;
+
+    use crate::message::MagnetI;
+    use crate::plane::PlaneFractal;
+
+    const MAGNET: MagnetI = MagnetI {
+        divergence_threshold_square: 100.0,
+        convergence_epsilon: 1e-3,
+    };
+
+    #[test]
+    fn test_only_orbits_without_bailout_have_full_count() {
+        let zero = Complex::new(0.0, 0.0);
+        // escapes at the first iteration, from z = 0
+        let (zn, count) = MAGNET.orbit(zero, Complex::new(2.001, 0.0), 1);
+        assert_eq!((zn, count), (0.0, 0.0));
+        // converges to 1 at the last iteration
+        let (zn, count) = MAGNET.orbit(zero, Complex::new(1e6, 0.0), 1);
+        assert!(zn > 0.5 && count < 1.0);
+        // neither
+        let (zn, count) = MAGNET.orbit(zero, Complex::new(0.0, 0.0), 1);
+        assert_eq!((zn, count), (0.0, 1.0));
+    }
+}
@@ -1,6 +1,20 @@
-use crate::message::{Mandelbrot, PixelIntensity, Range, Resolution};
+use crate::message::{Mandelbrot, PixelIntensity, Plane, Range, Resolution};
+use crate::orbit_trap::TrapDistance;
+use crate::perturbation::Perturbation;
+use crate::plane::PlaneFractal;
 use complex::complex::Complex;
 
+/// Square of the escape radius when estimating the distance: the estimate is only accurate
+/// once |z| is large
+pub(crate) const DISTANCE_ESTIMATION_THRESHOLD_SQUARE: f64 = 1e6;
+
+/// Exterior distance estimate of an escaped point of z^2 + c, from z_n and its derivative
+/// with respect to the pixel
+pub(crate) fn distance_estimate(z: Complex, dz: Complex) -> f64 {
+    let norm = z.norm();
+    0.5 * norm * norm.ln() / dz.norm()
+}
+
 impl Mandelbrot {
     /// Calculate the Mandelbrot set for a given resolution and range.
     /// is the maximum number of iterations to perform.
@@ -14,16 +28,22 @@ impl Mandelbrot {
     ) -> Vec<PixelIntensity> {
         let nx = resolution.nx;
         let ny = resolution.ny;
+        let pixel_size = (range.max.x - range.min.x) / nx as f64;
+        let one = Complex::new(1.0, 0.0);
 
         let mut pixels = Vec::new();
         for y in 0..ny {
             for x in 0..nx {
                 let minx = range.min.x + (range.max.x - range.min.x) * (x as f64 / nx as f64);
                 let miny = range.min.y + (range.max.y - range.min.y) * (y as f64 / ny as f64);
-                let result_all = Mandelbrot::calculate_all(
-                    Complex::new(minx, miny),
+                let c = Complex::new(minx, miny);
+                let result_all = self.calculate_all(
+                    c,
+                    one,
                     max_iteration,
-                    Complex::new(minx, miny),
+                    |z| z.square().add(c),
+                    one,
+                    pixel_size,
                 );
                 let pixel_intensity = PixelIntensity {
                     zn: result_all.0 as f32,
@@ -38,23 +58,87 @@ impl Mandelbrot {
     /// Calculate the Mandelbrot set for a given resolution and range.
     /// is the maximum number of iterations to perform.
     /// is the resolution of the image.
-    fn calculate_all(mut z: Complex, max_iteration: u16, c: Complex) -> (f64, f64) {
-        let mut count = 0;
+    /// `next` gives z_{n+1} = z_n^2 + c from z_n.
+    /// `dz` and `dc` are the derivatives of z_0 and c with respect to the pixel, tracked as
+    /// dz_{n+1} = 2 z_n dz_n + dc when estimating the distance to the set: `zn` is then the
+    /// distance in pixels, 0 inside the set.
+    fn calculate_all(
+        &self,
+        mut z: Complex,
+        mut dz: Complex,
+        max_iteration: u16,
+        mut next: impl FnMut(Complex) -> Complex,
+        dc: Complex,
+        pixel_size: f64,
+    ) -> (f64, f64) {
+        let threshold = if self.distance_estimation {
+            DISTANCE_ESTIMATION_THRESHOLD_SQUARE
+        } else {
+            4.0
+        };
         let mut zn_result: f64 = 0.0;
         let mut normalized_count: f64 = 0.0;
-        for _i in 0..max_iteration + 1 {
-            let tmp = z.square().add(c);
-            if z.norm_squared() > 4.0 {
-                return (zn_result, normalized_count);
+        let mut trap = TrapDistance::new(self.orbit_trap);
+        for count in 0..=max_iteration {
+            if z.norm_squared() > threshold {
+                if self.distance_estimation {
+                    return (distance_estimate(z, dz) / pixel_size, normalized_count);
+                }
+                return (trap.zn(zn_result), normalized_count);
             }
+            let tmp = next(z);
             if tmp.re.is_nan() || tmp.im.is_nan() || tmp.re.is_infinite() || tmp.im.is_infinite() {
-                return (zn_result, normalized_count);
+                return (trap.zn(zn_result), normalized_count);
             }
+            dz = dz * z * 2.0 + dc;
             z = tmp;
+            trap.visit(z);
             normalized_count = count as f64 / max_iteration as f64;
-            count += 1;
-            zn_result = z.norm_squared() / 4.0;
+            zn_result = z.norm_squared() / threshold;
+        }
+        if self.distance_estimation {
+            return (0.0, normalized_count);
         }
-        (zn_result, normalized_count)
+        (trap.zn(zn_result), normalized_count)
+    }
+}
+
+/// The Mandelbrot set is the parameter plane of z^2 + c, its dynamic plane being the Julia sets
+impl PlaneFractal for Mandelbrot {
+    fn default_plane(&self) -> Plane {
+        Plane::Parameter {
+            z0: Complex::new(0.0, 0.0),
+        }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        let (dz, dc) = Plane::Parameter { z0 }.derivatives();
+        self.calculate_all(z0, dz, max_iteration, |z| z.square().add(c), dc, 1.0)
+    }
+
+    fn calculate_pixel(
+        &self,
+        plane: Plane,
+        pixel: Complex,
+        pixel_size: f64,
+        max_iteration: u16,
+    ) -> (f64, f64) {
+        let (z0, c) = plane.start(pixel);
+        let (dz, dc) = plane.derivatives();
+        self.calculate_all(z0, dz, max_iteration, |z| z.square().add(c), dc, pixel_size)
+    }
+}
+
+impl Perturbation for Mandelbrot {
+    fn follow_orbit(
+        &self,
+        plane: Plane,
+        z0: Complex,
+        next: impl FnMut(Complex) -> Complex,
+        pixel_size: f64,
+        max_iteration: u16,
+    ) -> (f64, f64) {
+        let (dz, dc) = plane.derivatives();
+        self.calculate_all(z0, dz, max_iteration, next, dc, pixel_size)
     }
 }
@@ -0,0 +1,130 @@
+use crate::escape_time::EscapeTime;
+use crate::message::{Multibrot, Multijulia, OrbitTrap, PixelIntensity, Range, Resolution};
+use crate::plane::PlaneFractal;
+use complex::complex::Complex;
+
+/// Raise `z` to `power`, with binary exponentiation when the power is an integer
+/// and the polar form otherwise
+fn multibrot_power(z: Complex, power: f64) -> Complex {
+    if power.fract() == 0.0 && power.abs() <= i32::MAX as f64 {
+        z.powi(power as i32)
+    } else {
+        z.powf(power)
+    }
+}
+
+/// Escape radius of `z^power + c`: once `|z|` is above it the sequence diverges.
+/// It is at least 2 so that the smoothed count stays well defined.
+fn escape_radius(c: Complex, power: f64) -> f64 {
+    let radius = c.norm().max(2.0);
+    if power > 1.0 {
+        radius.max(2f64.powf(1.0 / (power - 1.0)))
+    } else {
+        radius
+    }
+}
+
+impl Multibrot {
+    /// Calculate the Multibrot set z^power + c for a given resolution and range.
+    pub fn calculate_multibrot(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_plane(self.default_plane(), max_iteration, resolution, range)
+    }
+}
+
+/// z_{n+1} = z_n^power + c, with z_0 = 0 and c the pixel.
+/// 0 has no negative power: for those the orbit goes from 0 to c, as if it started at z_0 = c.
+impl EscapeTime for Multibrot {
+    type State = ();
+
+    fn julia_c(&self) -> Option<Complex> {
+        None
+    }
+
+    fn orbit_trap(&self) -> Option<OrbitTrap> {
+        self.orbit_trap
+    }
+
+    fn iterate(&self, z: Complex, c: Complex, _state: &mut ()) -> Complex {
+        if self.power < 0.0 && z.norm_squared() == 0.0 {
+            return c;
+        }
+        multibrot_power(z, self.power) + c
+    }
+
+    fn divergence_threshold_square(&self, c: Complex) -> f64 {
+        escape_radius(c, self.power).powi(2)
+    }
+
+    fn smoothing_degree(&self) -> Option<f64> {
+        (self.power > 1.0).then_some(self.power)
+    }
+}
+
+impl Multijulia {
+    /// Calculate the Julia set of z^power + c for a given resolution and range.
+    pub fn calculate_multijulia(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_plane(self.default_plane(), max_iteration, resolution, range)
+    }
+}
+
+/// z_{n+1} = z_n^power + c, with z_0 the pixel
+impl EscapeTime for Multijulia {
+    type State = ();
+
+    fn julia_c(&self) -> Option<Complex> {
+        Some(self.c)
+    }
+
+    fn orbit_trap(&self) -> Option<OrbitTrap> {
+        self.orbit_trap
+    }
+
+    fn iterate(&self, z: Complex, c: Complex, _state: &mut ()) -> Complex {
+        multibrot_power(z, self.power) + c
+    }
+
+    /// The given threshold, raised to the escape radius when it is lower
+    fn divergence_threshold_square(&self, c: Complex) -> f64 {
+        self.divergence_threshold_square
+            .max(escape_radius(c, self.power).powi(2))
+    }
+
+    fn smoothing_degree(&self) -> Option<f64> {
+        (self.power > 1.0).then_some(self.power)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::{Multibrot, Point, Range, Resolution};
+
+    #[test]
+    fn test_negative_power_is_not_blank() {
+        let multibrot = Multibrot {
+            power: -2.0,
+            orbit_trap: None,
+        };
+        let range = Range {
+            min: Point { x: -2.0, y: -2.0 },
+            max: Point { x: 2.0, y: 2.0 },
+        };
+        let pixels = multibrot.calculate_multibrot(64, Resolution { nx: 16, ny: 16 }, range);
+        let escaped = pixels.iter().filter(|pixel| pixel.count < 1.0).count();
+        let bounded = pixels.iter().filter(|pixel| pixel.count == 1.0).count();
+        assert!(
+            escaped > 0 && bounded > 0,
+            "{escaped} escaped, {bounded} bounded"
+        );
+        assert!(pixels.iter().all(|pixel| pixel.zn.is_finite()));
+    }
+}
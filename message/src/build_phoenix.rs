@@ -0,0 +1,40 @@
+use crate::escape_time::EscapeTime;
+use crate::message::{OrbitTrap, Phoenix, PixelIntensity, Range, Resolution};
+use crate::plane::PlaneFractal;
+use complex::complex::Complex;
+
+impl Phoenix {
+    /// Calculate the Phoenix fractal for a given resolution and range.
+    pub fn calculate_phoenix(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        self.calculate_in_plane(self.default_plane(), max_iteration, resolution, range)
+    }
+}
+
+/// z_{n+1} = z_n^2 + c + p * z_{n-1}, with z_0 the pixel and z_{-1} = 0.
+/// The state is z_{n-1}.
+impl EscapeTime for Phoenix {
+    type State = Complex;
+
+    fn julia_c(&self) -> Option<Complex> {
+        Some(self.c)
+    }
+
+    fn orbit_trap(&self) -> Option<OrbitTrap> {
+        self.orbit_trap
+    }
+
+    fn iterate(&self, z: Complex, c: Complex, previous: &mut Complex) -> Complex {
+        let next = z.square() + c + self.p * *previous;
+        *previous = z;
+        next
+    }
+
+    fn divergence_threshold_square(&self, _c: Complex) -> f64 {
+        self.divergence_threshold_square
+    }
+}
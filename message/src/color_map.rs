@@ -0,0 +1,388 @@
+use std::f64::consts::TAU;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::message::PixelIntensity;
+
+/// Palette turning a position in [0, 1[ into a colour. Palettes are cyclic: the colour at 1 is
+/// the colour at 0, so that the offset and the cycle length can wrap them around.
+pub trait ColorMap: Send + Sync {
+    fn color(&self, t: f64) -> [u8; 3];
+}
+
+/// Number of bins of the histogram built by the equalization
+const EQUALIZATION_BINS: usize = 4096;
+
+/// Names of the built-in palettes, accepted by `named_color_map`
+pub const PALETTE_NAMES: [&str; 6] = ["cosine", "ultra", "fire", "ocean", "grayscale", "hsv"];
+
+/// The built-in palette of the given name
+pub fn named_color_map(name: &str) -> Option<Box<dyn ColorMap>> {
+    let color_map: Box<dyn ColorMap> = match name {
+        "cosine" => Box::new(CosinePalette::default()),
+        "ultra" => Box::new(Gradient::new(vec![
+            GradientStop::new(0.0, [0, 7, 100]),
+            GradientStop::new(0.16, [32, 107, 203]),
+            GradientStop::new(0.42, [237, 255, 255]),
+            GradientStop::new(0.6425, [255, 170, 0]),
+            GradientStop::new(0.8575, [0, 2, 0]),
+        ])),
+        "fire" => Box::new(Gradient::new(vec![
+            GradientStop::new(0.0, [0, 0, 0]),
+            GradientStop::new(0.3, [180, 20, 0]),
+            GradientStop::new(0.55, [255, 140, 0]),
+            GradientStop::new(0.75, [255, 240, 120]),
+            GradientStop::new(0.9, [255, 255, 255]),
+        ])),
+        "ocean" => Box::new(Gradient::new(vec![
+            GradientStop::new(0.0, [0, 10, 40]),
+            GradientStop::new(0.35, [0, 90, 150]),
+            GradientStop::new(0.6, [80, 200, 220]),
+            GradientStop::new(0.8, [230, 250, 255]),
+        ])),
+        "grayscale" => Box::new(Grayscale),
+        "hsv" => Box::new(HsvCycle),
+        _ => return None,
+    };
+    Some(color_map)
+}
+
+/// Cosine palette a + b * cos(2π (c t + d)), one term per channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CosinePalette {
+    pub a: [f64; 3],
+    pub b: [f64; 3],
+    pub c: [f64; 3],
+    pub d: [f64; 3],
+}
+
+impl Default for CosinePalette {
+    /// The blue and orange palette the images have always been drawn with
+    fn default() -> CosinePalette {
+        CosinePalette {
+            a: [0.5, 0.5, 0.5],
+            b: [0.5, 0.5, 0.5],
+            c: [1.0, 1.0, 1.0],
+            d: [0.0, 0.10, 0.20],
+        }
+    }
+}
+
+impl ColorMap for CosinePalette {
+    fn color(&self, t: f64) -> [u8; 3] {
+        let channel = |k: usize| {
+            let value = self.b[k] * (TAU * (self.c[k] * t + self.d[k])).cos() + self.a[k];
+            (255.0 * value) as u8
+        };
+        [channel(0), channel(1), channel(2)]
+    }
+}
+
+/// Colour of a gradient at a position in [0, 1[
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    pub position: f64,
+    pub color: [u8; 3],
+}
+
+impl GradientStop {
+    pub fn new(position: f64, color: [u8; 3]) -> GradientStop {
+        GradientStop { position, color }
+    }
+}
+
+/// Linear interpolation between colour stops, wrapping from the last stop to the first one
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Gradient {
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    /// Create a gradient, sorting the stops by position
+    pub fn new(mut stops: Vec<GradientStop>) -> Gradient {
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Gradient { stops }
+    }
+
+    /// Load a gradient from a JSON file such as
+    /// `{"stops": [{"position": 0.0, "color": [0, 7, 100]}, ...]}`
+    pub fn load(path: &str) -> Result<Gradient, String> {
+        let content =
+            fs::read_to_string(path).map_err(|err| format!("Cannot read {path}: {err}"))?;
+        let gradient: Gradient = serde_json::from_str(&content)
+            .map_err(|err| format!("Invalid gradient {path}: {err}"))?;
+        if gradient.stops.is_empty() {
+            return Err(format!("Invalid gradient {path}: no colour stop"));
+        }
+        if let Some(stop) = gradient
+            .stops
+            .iter()
+            .find(|stop| !(0.0..1.0).contains(&stop.position))
+        {
+            return Err(format!(
+                "Invalid gradient {path}: position {} is not in [0, 1[",
+                stop.position
+            ));
+        }
+        Ok(Gradient::new(gradient.stops))
+    }
+}
+
+impl ColorMap for Gradient {
+    fn color(&self, t: f64) -> [u8; 3] {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return [0, 0, 0],
+        };
+        let (from, to, span, offset) = match self.stops.windows(2).find(|pair| t < pair[1].position)
+        {
+            Some(pair) if t >= pair[0].position => (
+                &pair[0],
+                &pair[1],
+                pair[1].position - pair[0].position,
+                t - pair[0].position,
+            ),
+            _ => (
+                last,
+                first,
+                1.0 - last.position + first.position,
+                (t - last.position).rem_euclid(1.0),
+            ),
+        };
+        let fraction = if span > 0.0 { offset / span } else { 0.0 };
+        let channel = |k: usize| {
+            (from.color[k] as f64 + (to.color[k] as f64 - from.color[k] as f64) * fraction) as u8
+        };
+        [channel(0), channel(1), channel(2)]
+    }
+}
+
+/// Black to white
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grayscale;
+
+impl ColorMap for Grayscale {
+    fn color(&self, t: f64) -> [u8; 3] {
+        let level = (255.0 * t.clamp(0.0, 1.0)) as u8;
+        [level, level, level]
+    }
+}
+
+/// Full turn of the hue at full saturation and value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HsvCycle;
+
+impl ColorMap for HsvCycle {
+    fn color(&self, t: f64) -> [u8; 3] {
+        hsv_to_rgb(t, 1.0, 1.0)
+    }
+}
+
+/// Convert a colour from HSV, each component in [0, 1], to RGB
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> [u8; 3] {
+    let sector = hue.rem_euclid(1.0) * 6.0;
+    let fraction = sector.fract();
+    let p = value * (1.0 - saturation);
+    let q = value * (1.0 - saturation * fraction);
+    let r = value * (1.0 - saturation * (1.0 - fraction));
+    let (red, green, blue) = match sector as u8 {
+        0 => (value, r, p),
+        1 => (q, value, p),
+        2 => (p, value, r),
+        3 => (p, q, value),
+        4 => (r, p, value),
+        _ => (value, p, q),
+    };
+    [
+        (255.0 * red) as u8,
+        (255.0 * green) as u8,
+        (255.0 * blue) as u8,
+    ]
+}
+
+/// Field of `PixelIntensity` that drives the colour
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ColorSource {
+    #[default]
+    Zn,
+    Count,
+    /// `count` chooses the colour and `zn` its brightness
+    Both,
+}
+
+/// How the server colours the image: a palette, built-in or loaded from a gradient file,
+/// shifted by `offset` and repeated every `cycle` of the source value
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Coloring {
+    /// Name of a built-in palette, or path of a JSON gradient file
+    pub palette: String,
+    pub offset: f64,
+    pub cycle: f64,
+    pub source: ColorSource,
+    /// Spread the values of `count` evenly over the palette, through their cumulative
+    /// distribution over the whole image, for the sources `Count` and `Both`
+    pub equalize: bool,
+}
+
+impl Default for Coloring {
+    /// The cosine palette over `zn`, repeated twice, as the images have always been drawn
+    fn default() -> Coloring {
+        Coloring {
+            palette: "cosine".to_string(),
+            offset: 0.5,
+            cycle: 0.5,
+            source: ColorSource::Zn,
+            equalize: false,
+        }
+    }
+}
+
+impl Coloring {
+    /// The palette of the coloring, loading the gradient file when it is not a built-in one
+    pub fn color_map(&self) -> Result<Box<dyn ColorMap>, String> {
+        if self.cycle <= 0.0 {
+            return Err(format!("The cycle length {} is not positive", self.cycle));
+        }
+        if self.equalize && self.source == ColorSource::Zn {
+            return Err(
+                "The equalization spreads count over the palette, use the source Count or Both"
+                    .to_string(),
+            );
+        }
+        if let Some(color_map) = named_color_map(&self.palette) {
+            return Ok(color_map);
+        }
+        if self.palette.ends_with(".json") {
+            return Ok(Box::new(Gradient::load(&self.palette)?));
+        }
+        Err(format!(
+            "Unknown palette {}, expected a gradient file or one of {}",
+            self.palette,
+            PALETTE_NAMES.join(", ")
+        ))
+    }
+
+    /// Position in the palette of a value of the source
+    pub fn position(&self, value: f64) -> f64 {
+        (value / self.cycle + self.offset).rem_euclid(1.0)
+    }
+
+    /// Copy of the pixels with `count` replaced by the fraction of the image below it, in
+    /// [0, 1]. With `skip_interior`, the points that never escaped (`count` of 1) are left out
+    /// of the histogram and go to 1, so that the interior does not squeeze the other values;
+    /// the hits of a histogram fractal all count.
+    pub fn equalized(&self, pixels: &[PixelIntensity], skip_interior: bool) -> Vec<PixelIntensity> {
+        let is_counted = |pixel: &PixelIntensity| {
+            pixel.count.is_finite() && (!skip_interior || pixel.count < 1.0)
+        };
+
+        let (min, max) = pixels
+            .iter()
+            .filter(|pixel| is_counted(pixel))
+            .map(|pixel| pixel.count)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        if min >= max {
+            return pixels.to_vec();
+        }
+        let bin = |value: f32| {
+            (((value - min) / (max - min) * EQUALIZATION_BINS as f32) as usize)
+                .min(EQUALIZATION_BINS - 1)
+        };
+
+        let mut cumulative = vec![0usize; EQUALIZATION_BINS];
+        for pixel in pixels.iter().filter(|pixel| is_counted(pixel)) {
+            cumulative[bin(pixel.count)] += 1;
+        }
+        for k in 1..EQUALIZATION_BINS {
+            cumulative[k] += cumulative[k - 1];
+        }
+        let total = cumulative[EQUALIZATION_BINS - 1] as f32;
+
+        pixels
+            .iter()
+            .map(|pixel| PixelIntensity {
+                zn: pixel.zn,
+                count: if is_counted(pixel) {
+                    cumulative[bin(pixel.count)] as f32 / total
+                } else {
+                    1.0
+                },
+            })
+            .collect()
+    }
+
+    /// Colour of a pixel with the palette of the coloring
+    pub fn color(&self, color_map: &dyn ColorMap, pixel: &PixelIntensity) -> [u8; 3] {
+        match self.source {
+            ColorSource::Zn => color_map.color(self.position(pixel.zn as f64)),
+            ColorSource::Count => color_map.color(self.position(pixel.count as f64)),
+            ColorSource::Both => {
+                let brightness = 0.5 + 0.5 * (pixel.zn as f64).clamp(0.0, 1.0);
+                color_map
+                    .color(self.position(pixel.count as f64))
+                    .map(|channel| (channel as f64 * brightness) as u8)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColorSource, Coloring};
+    use crate::message::PixelIntensity;
+
+    fn pixels(counts: &[f32]) -> Vec<PixelIntensity> {
+        counts
+            .iter()
+            .map(|&count| PixelIntensity { zn: 0.25, count })
+            .collect()
+    }
+
+    fn counts(pixels: &[PixelIntensity]) -> Vec<f32> {
+        pixels.iter().map(|pixel| pixel.count).collect()
+    }
+
+    fn equalizing(source: ColorSource) -> Coloring {
+        Coloring {
+            source,
+            equalize: true,
+            ..Coloring::default()
+        }
+    }
+
+    #[test]
+    fn test_equalization_spreads_count() {
+        let coloring = equalizing(ColorSource::Count);
+        // most of the pixels at low counts, the interior at 1
+        let equalized = coloring.equalized(&pixels(&[0.01, 0.01, 0.02, 0.5, 1.0]), true);
+        assert_eq!(counts(&equalized), [0.5, 0.5, 0.75, 1.0, 1.0]);
+        assert!(equalized.iter().all(|pixel| pixel.zn == 0.25));
+    }
+
+    #[test]
+    fn test_equalization_counts_every_hit_of_a_histogram() {
+        let coloring = equalizing(ColorSource::Both);
+        let equalized = coloring.equalized(&pixels(&[0.0, 0.0, 1.0, 1.0, 3.0, 40.0]), false);
+        assert_eq!(
+            counts(&equalized),
+            [1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 5.0 / 6.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_uniform_image_is_left_as_it_is() {
+        let coloring = equalizing(ColorSource::Count);
+        let uniform = pixels(&[0.5, 0.5, 1.0]);
+        assert_eq!(coloring.equalized(&uniform, true), uniform);
+    }
+
+    #[test]
+    fn test_equalization_needs_count() {
+        assert!(equalizing(ColorSource::Zn).color_map().is_err());
+        assert!(equalizing(ColorSource::Count).color_map().is_ok());
+    }
+}
@@ -1,44 +1,158 @@
 extern crate image;
 
+use std::fs;
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+use crate::color_map::{hsv_to_rgb, ColorMap, Coloring, CosinePalette};
 use crate::message::PixelIntensity;
+use crate::png_metadata;
+use crate::scene::Scene;
+
+/// Encode an image in PNG with the scene in its metadata, and write it
+pub(crate) fn save_png_with_scene(
+    image: &DynamicImage,
+    filename: &str,
+    scene: &Scene,
+) -> Result<(), String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|err| format!("Cannot encode {filename}: {err}"))?;
+    let png = png_metadata::embed_scene(&png, scene)?;
+    fs::write(filename, png).map_err(|err| format!("Cannot write {filename}: {err}"))
+}
+
+/// Exponent of the darkening of the basins with the number of iterations
+const BASIN_SHADING: i32 = 3;
+
+/// How the pixel intensities are turned into colours
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorMode {
+    /// Palette of the coloring over its source field
+    #[default]
+    Palette,
+    /// Line art of the boundary from the distance to the set in pixels stored in `zn`:
+    /// black on the boundary and inside the set, white away from it
+    Boundary,
+    /// Palette of the coloring over the hits of a histogram fractal, whose equalization counts
+    /// every pixel since none of them is an interior point
+    Density,
+    /// Basins of a Newton fractal: the root the point converged to, stored in `zn` as a
+    /// fraction of a turn, gives the hue and the number of iterations the brightness
+    Basin,
+}
 
 /// Create the image from the pixel intensity
 /// and save it to a file
 pub fn create_image(
     width: u32,
     height: u32,
-    pixel_intensity_vec: &Vec<PixelIntensity>,
+    pixel_intensity_vec: &[PixelIntensity],
+    filename: String,
+    scene: &Scene,
+) {
+    create_image_with_mode(
+        width,
+        height,
+        pixel_intensity_vec,
+        filename,
+        ColorMode::Palette,
+        scene,
+    );
+}
+
+/// Create the image from the pixel intensity in the given colour mode
+/// and save it to a file
+pub fn create_image_with_mode(
+    width: u32,
+    height: u32,
+    pixel_intensity_vec: &[PixelIntensity],
     filename: String,
+    mode: ColorMode,
+    scene: &Scene,
 ) {
-    let image_width = width;
-    let image_height = height;
+    let image_buffer = color_image(
+        width,
+        height,
+        pixel_intensity_vec,
+        mode,
+        &Coloring::default(),
+        &CosinePalette::default(),
+    );
+    if let Err(err) = save_image(&image_buffer, filename, scene) {
+        println!("Error: {}", err);
+    }
+}
 
-    let mut image_buffer = image::ImageBuffer::new(image_width, image_height);
+/// Colour the pixel intensities into an image, with the palette of `coloring` in the
+/// palette mode, equalized over all the pixels given when it asks so
+pub fn color_image(
+    width: u32,
+    height: u32,
+    pixel_intensity_vec: &[PixelIntensity],
+    mode: ColorMode,
+    coloring: &Coloring,
+    color_map: &dyn ColorMap,
+) -> RgbImage {
+    let equalized;
+    let is_palette = matches!(mode, ColorMode::Palette | ColorMode::Density);
+    let pixel_intensity_vec = if is_palette && coloring.equalize {
+        equalized = coloring.equalized(pixel_intensity_vec, mode == ColorMode::Palette);
+        &equalized
+    } else {
+        pixel_intensity_vec
+    };
+    let mut image_buffer = RgbImage::new(width, height);
 
-    let mut count = 0;
-    for (_x, _y, pixel) in image_buffer.enumerate_pixels_mut() {
-        let t = pixel_intensity_vec[count].zn as f64;
-        *pixel = image::Rgb(color((2.0 * t + 0.5) % 1.0));
-        count += 1;
+    for (pixel, pixel_intensity) in image_buffer.pixels_mut().zip(pixel_intensity_vec) {
+        *pixel = image::Rgb(match mode {
+            ColorMode::Palette | ColorMode::Density => coloring.color(color_map, pixel_intensity),
+            ColorMode::Boundary => boundary_color(pixel_intensity.zn as f64),
+            ColorMode::Basin => basin_color(pixel_intensity),
+        });
     }
+    image_buffer
+}
 
-    let save = image_buffer.save(filename);
-    match save {
-        Ok(_) => {}
-        Err(e) => {
-            println!("Error: {:?}", e);
-        }
+/// Save an image to a file, its format given by the extension.
+/// A PNG image records the scene it was rendered from in a text chunk.
+pub fn save_image(image_buffer: &RgbImage, filename: String, scene: &Scene) -> Result<(), String> {
+    if filename.ends_with(".png") {
+        save_png_with_scene(
+            &DynamicImage::ImageRgb8(image_buffer.clone()),
+            &filename,
+            scene,
+        )
+    } else {
+        image_buffer
+            .save(&filename)
+            .map_err(|err| format!("Cannot write {filename}: {err}"))
     }
 }
 
 /// Create the color from the pixel intensity
 pub fn color(t: f64) -> [u8; 3] {
-    let a = (0.5, 0.5, 0.5);
-    let b = (0.5, 0.5, 0.5);
-    let c = (1.0, 1.0, 1.0);
-    let d = (0.0, 0.10, 0.20);
-    let r = b.0 * (6.28318 * (c.0 * t + d.0)).cos() + a.0;
-    let g = b.1 * (6.28318 * (c.1 * t + d.1)).cos() + a.1;
-    let b = b.2 * (6.28318 * (c.2 * t + d.2)).cos() + a.2;
-    [(255.0 * r) as u8, (255.0 * g) as u8, (255.0 * b) as u8]
+    CosinePalette::default().color(t)
+}
+
+/// Grey level of a pixel at `distance` pixels from the boundary, saturating at one pixel
+pub fn boundary_color(distance: f64) -> [u8; 3] {
+    let level = (255.0 * distance.clamp(0.0, 1.0).sqrt()) as u8;
+    [level, level, level]
+}
+
+/// Colour of a pixel of a Newton fractal: the hue of its root, darker as the convergence gets
+/// slower, black when it did not converge
+pub fn basin_color(pixel_intensity: &PixelIntensity) -> [u8; 3] {
+    let count = (pixel_intensity.count as f64).clamp(0.0, 1.0);
+    if count >= 1.0 {
+        return [0, 0, 0];
+    }
+    hsv_to_rgb(
+        pixel_intensity.zn as f64,
+        0.75,
+        (1.0 - count).powi(BASIN_SHADING),
+    )
 }
@@ -0,0 +1,84 @@
+use complex::complex::Complex;
+
+use crate::message::{OrbitTrap, Plane};
+use crate::orbit_trap::TrapDistance;
+use crate::plane::PlaneFractal;
+
+/// Escape-time fractal iterating `z_{n+1} = f(z_n, c)` until `|z_n|^2` exceeds the divergence
+/// threshold. In the parameter plane the pixel is `c` and `z_0 = 0`; in Julia mode `c` is
+/// fixed and the pixel is `z_0`.
+pub(crate) trait EscapeTime {
+    /// What a recurrence needs to remember besides `z_n`, such as `z_{n-1}`.
+    /// It starts at its default value for every pixel; `()` for one-step recurrences.
+    type State: Copy + Default;
+
+    /// The fixed `c` of the Julia mode, `None` for the parameter plane
+    fn julia_c(&self) -> Option<Complex>;
+
+    /// One step of the recurrence, updating the state alongside `z`
+    fn iterate(&self, z: Complex, c: Complex, state: &mut Self::State) -> Complex;
+
+    /// Square of the escape radius, which may depend on `c`
+    fn divergence_threshold_square(&self, _c: Complex) -> f64 {
+        4.0
+    }
+
+    /// Degree of the recurrence, when set the iteration count is smoothed
+    /// (the threshold must then be greater than 1)
+    fn smoothing_degree(&self) -> Option<f64> {
+        None
+    }
+
+    /// Trap the orbit is compared to, its distance then replacing `zn`
+    fn orbit_trap(&self) -> Option<OrbitTrap> {
+        None
+    }
+
+    /// Iterate from `z` and return `|z_n|^2 / threshold` for the last point inside the
+    /// threshold (or the distance to the orbit trap) and the number of iterations divided by
+    /// `max_iteration`
+    fn escape(&self, mut z: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        let threshold = self.divergence_threshold_square(c);
+        let mut zn_result: f64 = 0.0;
+        let mut normalized_count: f64 = 0.0;
+        let mut state = Self::State::default();
+        let mut trap = TrapDistance::new(self.orbit_trap());
+
+        for count in 0..=max_iteration {
+            if z.norm_squared() > threshold {
+                if let Some(degree) = self.smoothing_degree() {
+                    // Continuous count: subtract how far past the threshold z went, in iterations
+                    let overshoot = (z.norm_squared().ln() / threshold.ln()).ln() / degree.ln();
+                    let smooth_count = (count as f64 - overshoot).max(0.0);
+                    return (trap.zn(zn_result), smooth_count / max_iteration as f64);
+                }
+                return (trap.zn(zn_result), normalized_count);
+            }
+            let tmp = self.iterate(z, c, &mut state);
+            if tmp.is_nan() || tmp.is_infinite() {
+                return (trap.zn(zn_result), normalized_count);
+            }
+            z = tmp;
+            trap.visit(z);
+            normalized_count = count as f64 / max_iteration as f64;
+            zn_result = z.norm_squared() / threshold;
+        }
+        (trap.zn(zn_result), normalized_count)
+    }
+}
+
+/// Every escape-time fractal can be drawn in both planes, its Julia mode giving the default
+impl<T: EscapeTime> PlaneFractal for T {
+    fn default_plane(&self) -> Plane {
+        match self.julia_c() {
+            Some(c) => Plane::Dynamic { c },
+            None => Plane::Parameter {
+                z0: Complex::new(0.0, 0.0),
+            },
+        }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        self.escape(z0, c, max_iteration)
+    }
+}
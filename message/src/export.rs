@@ -0,0 +1,236 @@
+use image::{DynamicImage, ImageBuffer, Luma, Rgb};
+
+use crate::color_map::{ColorMap, ColorSource};
+use crate::drawing_image::{color_image, save_image, save_png_with_scene};
+use crate::message::PixelIntensity;
+use crate::scene::Scene;
+
+/// Kind of file written for an image, chosen from the extension of its name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// Coloured 8-bit RGB image: PNG (with the scene in its metadata), PPM, or any other format
+    /// of the `image` crate
+    Color,
+    /// Raw intensities in a 16-bit RGB PNG: `zn` in red, `count` in green, clamped to [0, 1]
+    Png16,
+    /// Raw intensities in a 16-bit RGB TIFF, as `Png16` (the `image` crate does not write
+    /// float TIFF)
+    Tiff16,
+    /// Raw intensities in a 32-bit float OpenEXR: `zn` in red, `count` in green, unclamped
+    Exr,
+    /// Raw source value of the coloring (`count` for `Both`), clamped to [0, 1], in an
+    /// 8-bit PGM
+    Pgm,
+}
+
+impl ExportFormat {
+    /// The format of a file name: `.tif`/`.tiff`, `.exr` and `.pgm` hold raw intensities,
+    /// and so does `.png` when `sixteen_bit` is set
+    pub fn from_filename(filename: &str, sixteen_bit: bool) -> ExportFormat {
+        let extension = filename
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "png" if sixteen_bit => ExportFormat::Png16,
+            "tif" | "tiff" => ExportFormat::Tiff16,
+            "exr" => ExportFormat::Exr,
+            "pgm" => ExportFormat::Pgm,
+            _ => ExportFormat::Color,
+        }
+    }
+}
+
+/// Write the pixel intensities of the scene to a file in the format of its name, coloured
+/// with `color_map` and the coloring of the scene, or raw
+pub fn export_image(
+    filename: &str,
+    sixteen_bit: bool,
+    pixel_intensity_vec: &[PixelIntensity],
+    scene: &Scene,
+    color_map: &dyn ColorMap,
+) -> Result<(), String> {
+    let width = scene.resolution.nx as u32;
+    let height = scene.resolution.ny as u32;
+    let pixel = |x: u32, y: u32| {
+        pixel_intensity_vec
+            .get(y as usize * width as usize + x as usize)
+            .cloned()
+            .unwrap_or(PixelIntensity {
+                zn: 0.0,
+                count: 0.0,
+            })
+    };
+    let to_u16 = |value: f32| (value.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+    let raw16 = || {
+        DynamicImage::ImageRgb16(ImageBuffer::from_fn(width, height, |x, y| {
+            let pixel = pixel(x, y);
+            Rgb([to_u16(pixel.zn), to_u16(pixel.count), 0])
+        }))
+    };
+
+    let image = match ExportFormat::from_filename(filename, sixteen_bit) {
+        ExportFormat::Color => {
+            let image = color_image(
+                width,
+                height,
+                pixel_intensity_vec,
+                scene.fractal.color_mode(),
+                &scene.coloring(),
+                color_map,
+            );
+            return save_image(&image, filename.to_string(), scene);
+        }
+        ExportFormat::Png16 => return save_png_with_scene(&raw16(), filename, scene),
+        ExportFormat::Tiff16 => raw16(),
+        ExportFormat::Exr => {
+            DynamicImage::ImageRgb32F(ImageBuffer::from_fn(width, height, |x, y| {
+                let pixel = pixel(x, y);
+                Rgb([pixel.zn, pixel.count, 0.0])
+            }))
+        }
+        ExportFormat::Pgm => {
+            let source = scene.coloring().source;
+            DynamicImage::ImageLuma8(ImageBuffer::from_fn(width, height, |x, y| {
+                let pixel = pixel(x, y);
+                let value = match source {
+                    ColorSource::Zn => pixel.zn,
+                    ColorSource::Count | ColorSource::Both => pixel.count,
+                };
+                Luma([(value.clamp(0.0, 1.0) * u8::MAX as f32) as u8])
+            }))
+        }
+    };
+    image
+        .save(filename)
+        .map_err(|err| format!("Cannot write {filename}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::{export_image, ExportFormat};
+    use crate::color_map::CosinePalette;
+    use crate::message::PixelIntensity;
+    use crate::png_metadata;
+    use crate::scene::Scene;
+
+    /// A 2x1 Mandelbrot scene colouring `count`
+    fn scene() -> Scene {
+        serde_json::from_str(
+            r#"{
+                "fractal": {"Mandelbrot": {}},
+                "max_iteration": 16,
+                "resolution": {"nx": 2, "ny": 1},
+                "range": {"min": {"x": -2.0, "y": -1.0}, "max": {"x": 1.0, "y": 1.0}},
+                "coloring": {"source": "Count"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    const PIXELS: [PixelIntensity; 2] = [
+        PixelIntensity {
+            zn: 0.25,
+            count: 1.0,
+        },
+        PixelIntensity {
+            zn: 2.5,
+            count: 0.5,
+        },
+    ];
+
+    /// Export the pixels to a file of the given name in a temporary directory, and read it back
+    fn export(name: &str, sixteen_bit: bool) -> (Vec<u8>, image::DynamicImage) {
+        let path = env::temp_dir().join(format!("export_{}_{name}", std::process::id()));
+        let filename = path.to_str().unwrap();
+        export_image(
+            filename,
+            sixteen_bit,
+            &PIXELS,
+            &scene(),
+            &CosinePalette::default(),
+        )
+        .unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let image = image::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        (bytes, image)
+    }
+
+    #[test]
+    fn test_format_from_filename() {
+        assert_eq!(
+            ExportFormat::from_filename("a.png", false),
+            ExportFormat::Color
+        );
+        assert_eq!(
+            ExportFormat::from_filename("a.PNG", true),
+            ExportFormat::Png16
+        );
+        assert_eq!(
+            ExportFormat::from_filename("a.tif", false),
+            ExportFormat::Tiff16
+        );
+        assert_eq!(
+            ExportFormat::from_filename("a.exr", true),
+            ExportFormat::Exr
+        );
+        assert_eq!(
+            ExportFormat::from_filename("a.pgm", false),
+            ExportFormat::Pgm
+        );
+        assert_eq!(
+            ExportFormat::from_filename("a.ppm", true),
+            ExportFormat::Color
+        );
+        assert_eq!(
+            ExportFormat::from_filename("png", false),
+            ExportFormat::Color
+        );
+    }
+
+    #[test]
+    fn test_coloured_png_records_the_scene() {
+        let (bytes, image) = export("color.png", false);
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(png_metadata::read_scene(&bytes).unwrap(), scene());
+    }
+
+    #[test]
+    fn test_raw_formats_hold_the_intensities() {
+        let (bytes, png16) = export("raw.png", true);
+        let png16 = png16.to_rgb16();
+        assert_eq!(png16.get_pixel(0, 0).0, [16383, 65535, 0]);
+        // zn clamped to 1
+        assert_eq!(png16.get_pixel(1, 0).0, [65535, 32767, 0]);
+        assert_eq!(png_metadata::read_scene(&bytes).unwrap(), scene());
+
+        let (_, tiff) = export("raw.tiff", false);
+        assert_eq!(tiff.to_rgb16(), png16);
+
+        let (_, exr) = export("raw.exr", false);
+        let exr = exr.to_rgb32f();
+        assert_eq!(exr.get_pixel(1, 0).0, [2.5, 0.5, 0.0]);
+
+        let (_, pgm) = export("raw.pgm", false);
+        assert_eq!(pgm.to_luma8().into_raw(), [255, 127]);
+    }
+
+    #[test]
+    fn test_write_errors_are_returned() {
+        for name in ["color.png", "color.ppm", "raw.exr"] {
+            let filename = format!("/nonexistent/directory/{name}");
+            let result = export_image(
+                &filename,
+                false,
+                &PIXELS,
+                &scene(),
+                &CosinePalette::default(),
+            );
+            assert!(result.unwrap_err().starts_with("Cannot write"), "{name}");
+        }
+    }
+}
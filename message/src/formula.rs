@@ -0,0 +1,603 @@
+use std::f64::consts::{E, PI};
+
+use complex::complex::Complex;
+
+/// Values of the variables available in a formula
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Variables {
+    /// Current term of the sequence, `z`
+    pub z: Complex,
+    /// Previous term of the sequence, `z_prev`, zero before the first iteration
+    pub previous_z: Complex,
+    /// Parameter `c`, the pixel unless the plane fixes it
+    pub c: Complex,
+    /// Coordinate of the pixel, `pixel`
+    pub pixel: Complex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    Z,
+    PreviousZ,
+    C,
+    Pixel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Sqrt,
+    Conj,
+    Abs,
+    Re,
+    Im,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            "sinh" => Some(Function::Sinh),
+            "cosh" => Some(Function::Cosh),
+            "tanh" => Some(Function::Tanh),
+            "exp" => Some(Function::Exp),
+            "ln" | "log" => Some(Function::Ln),
+            "sqrt" => Some(Function::Sqrt),
+            "conj" => Some(Function::Conj),
+            "abs" => Some(Function::Abs),
+            "re" => Some(Function::Re),
+            "im" => Some(Function::Im),
+            _ => None,
+        }
+    }
+
+    fn apply(self, z: Complex) -> Complex {
+        match self {
+            Function::Sin => z.sin(),
+            Function::Cos => z.cos(),
+            Function::Tan => z.tan(),
+            Function::Sinh => z.sinh(),
+            Function::Cosh => z.cosh(),
+            Function::Tanh => z.tanh(),
+            Function::Exp => z.exp(),
+            Function::Ln => z.ln(),
+            Function::Sqrt => z.sqrt(),
+            Function::Conj => z.conj(),
+            Function::Abs => Complex::new(z.norm(), 0.0),
+            Function::Re => Complex::new(z.re, 0.0),
+            Function::Im => Complex::new(z.im, 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinaryOperator {
+    fn apply(self, left: Complex, right: Complex) -> Complex {
+        match self {
+            BinaryOperator::Add => left + right,
+            BinaryOperator::Sub => left - right,
+            BinaryOperator::Mul => left * right,
+            BinaryOperator::Div => left / right,
+            BinaryOperator::Pow => left.powc(right),
+        }
+    }
+}
+
+/// Syntax tree of an expression
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    Constant(Complex),
+    Variable(Variable),
+    Negate(Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    Call(Function, Box<Expression>),
+}
+
+impl Expression {
+    /// Build a negation, computed right away when the operand is constant
+    fn negate(operand: Expression) -> Expression {
+        match operand {
+            Expression::Constant(value) => Expression::Constant(-value),
+            operand => Expression::Negate(Box::new(operand)),
+        }
+    }
+
+    /// Build a binary operation, computed right away when both operands are constant
+    fn binary(operator: BinaryOperator, left: Expression, right: Expression) -> Expression {
+        match (&left, &right) {
+            (Expression::Constant(left), Expression::Constant(right)) => {
+                Expression::Constant(operator.apply(*left, *right))
+            }
+            _ => Expression::Binary(operator, Box::new(left), Box::new(right)),
+        }
+    }
+
+    /// Build a function call, computed right away when the argument is constant
+    fn call(function: Function, argument: Expression) -> Expression {
+        match argument {
+            Expression::Constant(value) => Expression::Constant(function.apply(value)),
+            argument => Expression::Call(function, Box::new(argument)),
+        }
+    }
+}
+
+/// Instruction of the stack machine evaluating a formula
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Instruction {
+    Push(Complex),
+    Load(Variable),
+    Negate,
+    Binary(BinaryOperator),
+    /// Power by a constant integer, with binary exponentiation
+    Powi(i32),
+    Call(Function),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Imaginary(f64),
+    Identifier(String),
+    Operator(char),
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+/// Split the expression into tokens, with their position in the expression
+fn tokenize(expression: &str) -> Result<Vec<(Token, usize)>, String> {
+    let characters: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < characters.len() {
+        let character = characters[position];
+        let start = position;
+        if character.is_whitespace() {
+            position += 1;
+        } else if character.is_ascii_digit() || character == '.' {
+            while position < characters.len()
+                && (characters[position].is_ascii_digit() || characters[position] == '.')
+            {
+                position += 1;
+            }
+            // Exponent of a scientific notation, such as 1e-3
+            let is_digit = |index: usize| characters.get(index).is_some_and(char::is_ascii_digit);
+            let sign_length = match characters.get(position + 1) {
+                Some('+' | '-') => 1,
+                _ => 0,
+            };
+            if matches!(characters.get(position), Some('e' | 'E'))
+                && is_digit(position + 1 + sign_length)
+            {
+                position += 1 + sign_length;
+                while position < characters.len() && characters[position].is_ascii_digit() {
+                    position += 1;
+                }
+            }
+            let text: String = characters[start..position].iter().collect();
+            let value: f64 = text
+                .parse()
+                .map_err(|_| format!("Invalid number '{text}' at position {start}"))?;
+            let is_imaginary = position < characters.len()
+                && characters[position] == 'i'
+                && !characters
+                    .get(position + 1)
+                    .is_some_and(|next| next.is_ascii_alphanumeric() || *next == '_');
+            if is_imaginary {
+                position += 1;
+                tokens.push((Token::Imaginary(value), start));
+            } else {
+                tokens.push((Token::Number(value), start));
+            }
+        } else if character.is_ascii_alphabetic() || character == '_' {
+            while position < characters.len()
+                && (characters[position].is_ascii_alphanumeric() || characters[position] == '_')
+            {
+                position += 1;
+            }
+            let name: String = characters[start..position].iter().collect();
+            tokens.push((Token::Identifier(name.to_ascii_lowercase()), start));
+        } else {
+            let token = match character {
+                '+' | '-' | '*' | '/' | '^' => Token::Operator(character),
+                '(' => Token::OpenParenthesis,
+                ')' => Token::CloseParenthesis,
+                _ => {
+                    return Err(format!(
+                        "Unexpected character '{character}' at position {start}"
+                    ))
+                }
+            };
+            tokens.push((token, start));
+            position += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Deepest nesting of parentheses, function calls, signs and exponents, so that a formula
+/// cannot overflow the stack of the recursive parser
+const MAX_NESTING: usize = 100;
+
+/// Most binary operations in a formula, so that a long chain such as `z + z + ...` cannot
+/// overflow the stack when its tree is compiled or dropped
+const MAX_OPERATIONS: usize = 1000;
+
+/// Recursive descent parser, from the lowest to the highest precedence:
+/// `+ -`, `* /`, unary `-`, `^` (right associative), then numbers, variables,
+/// function calls and parentheses
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    length: usize,
+    /// Number of `parse_unary` calls in progress, every nesting going through it
+    nesting: usize,
+    /// Number of binary operations parsed so far
+    operations: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    /// Position in the expression of the next token, for the error messages
+    fn location(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|&(_, location)| location)
+            .unwrap_or(self.length)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn expect_close_parenthesis(&mut self) -> Result<(), String> {
+        let location = self.location();
+        match self.next() {
+            Some(Token::CloseParenthesis) => Ok(()),
+            _ => Err(format!("Expected ')' at position {location}")),
+        }
+    }
+
+    /// Count a binary operation, or tell that the formula has too many of them
+    fn count_operation(&mut self) -> Result<(), String> {
+        if self.operations == MAX_OPERATIONS {
+            return Err(format!(
+                "Formula too long at position {}, at most {MAX_OPERATIONS} operations",
+                self.location()
+            ));
+        }
+        self.operations += 1;
+        Ok(())
+    }
+
+    fn parse_sum(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_product()?;
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = self.peek() {
+            let operator = if *operator == '+' {
+                BinaryOperator::Add
+            } else {
+                BinaryOperator::Sub
+            };
+            self.count_operation()?;
+            self.position += 1;
+            expression = Expression::binary(operator, expression, self.parse_product()?);
+        }
+        Ok(expression)
+    }
+
+    fn parse_product(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_unary()?;
+        while let Some(Token::Operator(operator @ ('*' | '/'))) = self.peek() {
+            let operator = if *operator == '*' {
+                BinaryOperator::Mul
+            } else {
+                BinaryOperator::Div
+            };
+            self.count_operation()?;
+            self.position += 1;
+            expression = Expression::binary(operator, expression, self.parse_unary()?);
+        }
+        Ok(expression)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        if self.nesting == MAX_NESTING {
+            return Err(format!(
+                "Formula nested too deeply at position {}",
+                self.location()
+            ));
+        }
+        self.nesting += 1;
+        let expression = self.parse_signed();
+        self.nesting -= 1;
+        expression
+    }
+
+    fn parse_signed(&mut self) -> Result<Expression, String> {
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.position += 1;
+                Ok(Expression::negate(self.parse_unary()?))
+            }
+            Some(Token::Operator('+')) => {
+                self.position += 1;
+                self.parse_unary()
+            }
+            _ => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Expression, String> {
+        let base = self.parse_primary()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.count_operation()?;
+            self.position += 1;
+            let exponent = self.parse_unary()?;
+            return Ok(Expression::binary(BinaryOperator::Pow, base, exponent));
+        }
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        let location = self.location();
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expression::Constant(Complex::new(value, 0.0))),
+            Some(Token::Imaginary(value)) => Ok(Expression::Constant(Complex::new(0.0, value))),
+            Some(Token::OpenParenthesis) => {
+                let expression = self.parse_sum()?;
+                self.expect_close_parenthesis()?;
+                Ok(expression)
+            }
+            Some(Token::Identifier(name)) => {
+                if let Some(function) = Function::from_name(&name) {
+                    if self.next() != Some(Token::OpenParenthesis) {
+                        return Err(format!(
+                            "Expected '(' after the function '{name}' at position {location}"
+                        ));
+                    }
+                    let argument = self.parse_sum()?;
+                    self.expect_close_parenthesis()?;
+                    return Ok(Expression::call(function, argument));
+                }
+                match name.as_str() {
+                    "z" => Ok(Expression::Variable(Variable::Z)),
+                    "z_prev" => Ok(Expression::Variable(Variable::PreviousZ)),
+                    "c" => Ok(Expression::Variable(Variable::C)),
+                    "pixel" => Ok(Expression::Variable(Variable::Pixel)),
+                    "i" => Ok(Expression::Constant(Complex::new(0.0, 1.0))),
+                    "pi" => Ok(Expression::Constant(Complex::new(PI, 0.0))),
+                    "e" => Ok(Expression::Constant(Complex::new(E, 0.0))),
+                    _ => Err(format!("Unknown name '{name}' at position {location}")),
+                }
+            }
+            Some(token) => Err(format!("Unexpected {token:?} at position {location}")),
+            None => Err(format!(
+                "Unexpected end of expression at position {location}"
+            )),
+        }
+    }
+}
+
+/// Formula over complex numbers, parsed once and evaluated for every iteration of every pixel
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Formula {
+    instructions: Vec<Instruction>,
+}
+
+impl Formula {
+    /// Parse an expression such as `z^3 + c*sin(z)`, with the variables `z`, `z_prev`,
+    /// `c` and `pixel`, the constants `i`, `pi` and `e` and the usual complex functions
+    pub fn parse(expression: &str) -> Result<Formula, String> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            length: expression.chars().count(),
+            nesting: 0,
+            operations: 0,
+        };
+        let tree = parser.parse_sum()?;
+        if parser.position < parser.tokens.len() {
+            return Err(format!(
+                "Unexpected {:?} at position {}",
+                parser.tokens[parser.position].0,
+                parser.location()
+            ));
+        }
+
+        let mut instructions = Vec::new();
+        Self::compile(&tree, &mut instructions);
+        Ok(Formula { instructions })
+    }
+
+    /// Write the instructions of the tree in postfix order
+    fn compile(expression: &Expression, instructions: &mut Vec<Instruction>) {
+        match expression {
+            Expression::Constant(value) => instructions.push(Instruction::Push(*value)),
+            Expression::Variable(variable) => instructions.push(Instruction::Load(*variable)),
+            Expression::Negate(operand) => {
+                Self::compile(operand, instructions);
+                instructions.push(Instruction::Negate);
+            }
+            Expression::Binary(BinaryOperator::Pow, base, exponent) => {
+                Self::compile(base, instructions);
+                match **exponent {
+                    Expression::Constant(value)
+                        if value.im == 0.0
+                            && value.re.fract() == 0.0
+                            && value.re.abs() <= i32::MAX as f64 =>
+                    {
+                        instructions.push(Instruction::Powi(value.re as i32))
+                    }
+                    _ => {
+                        Self::compile(exponent, instructions);
+                        instructions.push(Instruction::Binary(BinaryOperator::Pow));
+                    }
+                }
+            }
+            Expression::Binary(operator, left, right) => {
+                Self::compile(left, instructions);
+                Self::compile(right, instructions);
+                instructions.push(Instruction::Binary(*operator));
+            }
+            Expression::Call(function, argument) => {
+                Self::compile(argument, instructions);
+                instructions.push(Instruction::Call(*function));
+            }
+        }
+    }
+
+    /// Evaluate the formula, `stack` being reused between the calls to avoid allocations
+    pub fn eval(&self, variables: &Variables, stack: &mut Vec<Complex>) -> Complex {
+        stack.clear();
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Push(value) => stack.push(value),
+                Instruction::Load(variable) => stack.push(match variable {
+                    Variable::Z => variables.z,
+                    Variable::PreviousZ => variables.previous_z,
+                    Variable::C => variables.c,
+                    Variable::Pixel => variables.pixel,
+                }),
+                Instruction::Negate => {
+                    let operand = stack.pop().unwrap();
+                    stack.push(-operand);
+                }
+                Instruction::Binary(operator) => {
+                    let right = stack.pop().unwrap();
+                    let left = stack.pop().unwrap();
+                    stack.push(operator.apply(left, right));
+                }
+                Instruction::Powi(exponent) => {
+                    let base = stack.pop().unwrap();
+                    stack.push(base.powi(exponent));
+                }
+                Instruction::Call(function) => {
+                    let argument = stack.pop().unwrap();
+                    stack.push(function.apply(argument));
+                }
+            }
+        }
+        stack.pop().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use complex::complex::Complex;
+
+    use super::{Formula, Instruction, Variables, MAX_NESTING, MAX_OPERATIONS};
+
+    /// Value of the formula for the given `z` and `c`
+    fn eval(expression: &str, z: Complex, c: Complex) -> Complex {
+        let variables = Variables {
+            z,
+            previous_z: Complex::new(0.0, 0.0),
+            c,
+            pixel: c,
+        };
+        Formula::parse(expression)
+            .unwrap()
+            .eval(&variables, &mut Vec::new())
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        let z = Complex::new(0.5, -1.5);
+        let c = Complex::new(-0.25, 0.75);
+        assert_eq!(eval("z^2 + c", z, c), z.square() + c);
+        assert_eq!(eval("-z^2", z, c), -z.square());
+        assert!((eval("2^3^2", z, c) - Complex::new(512.0, 0.0)).norm() < 1e-9);
+        assert_eq!(eval("8 - 2 - 1", z, c), Complex::new(5.0, 0.0));
+        assert_eq!(eval("12 / 3 / 2", z, c), Complex::new(2.0, 0.0));
+        assert_eq!(eval("c*(z + 1)", z, c), c * (z + 1.0));
+        assert_eq!(
+            eval("conj(z) + 2i", z, c),
+            z.conj() + Complex::new(0.0, 2.0)
+        );
+        assert_eq!(eval("1.5e-1 + 1E2", z, c), Complex::new(100.15, 0.0));
+    }
+
+    #[test]
+    fn test_constants_are_folded() {
+        let formula = Formula::parse("z^(1 + 2) + 2*pi - -sin(0)").unwrap();
+        assert_eq!(
+            formula.instructions,
+            [
+                Instruction::Load(super::Variable::Z),
+                Instruction::Powi(3),
+                Instruction::Push(Complex::new(2.0 * std::f64::consts::PI, 0.0)),
+                Instruction::Binary(super::BinaryOperator::Add),
+                Instruction::Push(Complex::new(0.0, 0.0)),
+                Instruction::Binary(super::BinaryOperator::Sub),
+            ]
+        );
+        let constant = Formula::parse("(1 + i) * (1 - i) * i").unwrap();
+        assert_eq!(
+            constant.instructions,
+            [Instruction::Push(Complex::new(0.0, 2.0))]
+        );
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let error = |expression: &str| Formula::parse(expression).unwrap_err();
+        assert_eq!(error("z + w"), "Unknown name 'w' at position 4");
+        assert_eq!(
+            error("sin z"),
+            "Expected '(' after the function 'sin' at position 0"
+        );
+        assert_eq!(error("(z + c"), "Expected ')' at position 6");
+        assert_eq!(error("z $ c"), "Unexpected character '$' at position 2");
+        assert_eq!(error("z *"), "Unexpected end of expression at position 3");
+        assert_eq!(error("z c"), "Unexpected Identifier(\"c\") at position 2");
+        assert_eq!(error("1..2"), "Invalid number '1..2' at position 0");
+    }
+
+    #[test]
+    fn test_nesting_is_limited() {
+        let nested = |depth: usize| format!("{}z{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Formula::parse(&nested(MAX_NESTING - 1)).is_ok());
+        for expression in [nested(100_000), "-".repeat(100_000) + "z"] {
+            let error = Formula::parse(&expression).unwrap_err();
+            assert!(error.starts_with("Formula nested too deeply"), "{error}");
+        }
+        let powers = vec!["z"; 100_000].join("^");
+        assert!(Formula::parse(&powers).is_err());
+    }
+
+    #[test]
+    fn test_length_is_limited() {
+        let chain = |length: usize| vec!["z"; length].join("+");
+        assert!(Formula::parse(&chain(MAX_OPERATIONS + 1)).is_ok());
+        for expression in [chain(100_000), vec!["z"; 100_000].join("*")] {
+            let error = Formula::parse(&expression).unwrap_err();
+            assert!(error.starts_with("Formula too long"), "{error}");
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+
+use crate::message::PixelIntensity;
+use crate::scene::Scene;
+
+/// Magic bytes at the start of a .frakt file
+const MAGIC: &[u8; 5] = b"FRAKT";
+
+/// Version of the layout, written after the magic bytes
+const VERSION: u8 = 1;
+
+/// Size of a `PixelIntensity` in the file, two big-endian f32 as on the wire
+const PIXEL_SIZE: usize = 8;
+
+/// Raw result of a render: the scene and the pixel intensities of the whole image, so that it
+/// can be coloured again without being computed again.
+///
+/// Layout of a .frakt file, every integer and float in big-endian:
+/// `FRAKT` | version (u8) | scene JSON size (u32) | scene JSON | (zn f32, count f32) * nx * ny
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frakt {
+    pub scene: Scene,
+    pub pixels: Vec<PixelIntensity>,
+}
+
+impl Frakt {
+    /// Encode the scene and the pixels
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        Frakt::encode(&self.scene, &self.pixels)
+    }
+
+    /// Encode a scene and its pixels, without gathering them in a `Frakt`
+    pub fn encode(scene: &Scene, pixels: &[PixelIntensity]) -> Result<Vec<u8>, String> {
+        let json =
+            serde_json::to_vec(scene).map_err(|err| format!("Cannot serialize scene: {err}"))?;
+        let mut bytes =
+            Vec::with_capacity(MAGIC.len() + 5 + json.len() + pixels.len() * PIXEL_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(json.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&json);
+        for pixel in pixels {
+            bytes.extend_from_slice(&pixel.zn.to_be_bytes());
+            bytes.extend_from_slice(&pixel.count.to_be_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Decode a .frakt file, checking that it holds one pixel per pixel of the scene
+    pub fn from_bytes(bytes: &[u8]) -> Result<Frakt, String> {
+        let header_size = MAGIC.len() + 5;
+        if bytes.len() < header_size || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("Not a .frakt file".to_string());
+        }
+        let version = bytes[MAGIC.len()];
+        if version != VERSION {
+            return Err(format!("Unsupported .frakt version {version}"));
+        }
+        let json_size = u32::from_be_bytes([
+            bytes[MAGIC.len() + 1],
+            bytes[MAGIC.len() + 2],
+            bytes[MAGIC.len() + 3],
+            bytes[MAGIC.len() + 4],
+        ]) as usize;
+        let json = bytes
+            .get(header_size..header_size + json_size)
+            .ok_or("Truncated .frakt scene")?;
+        let scene: Scene =
+            serde_json::from_slice(json).map_err(|err| format!("Invalid .frakt scene: {err}"))?;
+
+        let data = &bytes[header_size + json_size..];
+        let expected = scene.resolution.nx as usize * scene.resolution.ny as usize;
+        if data.len() != expected * PIXEL_SIZE {
+            return Err(format!(
+                "The .frakt file holds {} bytes of pixels instead of {} for {}x{}",
+                data.len(),
+                expected * PIXEL_SIZE,
+                scene.resolution.nx,
+                scene.resolution.ny
+            ));
+        }
+        let pixels = data
+            .chunks_exact(PIXEL_SIZE)
+            .map(|chunk| PixelIntensity {
+                zn: f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                count: f32::from_be_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+            })
+            .collect();
+        Ok(Frakt { scene, pixels })
+    }
+
+    /// Save to a .frakt file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        Frakt::write(path, &self.scene, &self.pixels)
+    }
+
+    /// Save a scene and its pixels to a .frakt file
+    pub fn write(path: &str, scene: &Scene, pixels: &[PixelIntensity]) -> Result<(), String> {
+        let bytes = Frakt::encode(scene, pixels)?;
+        fs::write(path, bytes).map_err(|err| format!("Cannot write {path}: {err}"))
+    }
+
+    /// Load a .frakt file
+    pub fn load(path: &str) -> Result<Frakt, String> {
+        let bytes = fs::read(path).map_err(|err| format!("Cannot read {path}: {err}"))?;
+        Frakt::from_bytes(&bytes).map_err(|err| format!("Invalid {path}: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Frakt, MAGIC};
+    use crate::message::PixelIntensity;
+    use crate::scene::Scene;
+
+    /// A 3x2 deep zoom with a coloring, and its pixels
+    fn frakt() -> Frakt {
+        let scene: Scene = serde_json::from_str(
+            r#"{
+                "fractal": {"Mandelbrot": {"distance_estimation": true}},
+                "max_iteration": 500,
+                "resolution": {"nx": 3, "ny": 2},
+                "range": {"min": {"x": -1e-20, "y": -1e-20}, "max": {"x": 1e-20, "y": 1e-20}},
+                "center": {"re": "-0.7436438870371587047521915061148", "im": "0.13182590420531197"},
+                "coloring": {"palette": "fire", "source": "Count", "equalize": true}
+            }"#,
+        )
+        .unwrap();
+        let pixels = [0.0, 1.0, f32::MIN_POSITIVE, -2.5, 1e30, f32::NAN]
+            .iter()
+            .map(|&zn| PixelIntensity { zn, count: 0.125 })
+            .collect();
+        Frakt { scene, pixels }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let frakt = frakt();
+        let decoded = Frakt::from_bytes(&frakt.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.scene, frakt.scene);
+        // NaN is not equal to itself, the bits are compared instead
+        let bits = |frakt: &Frakt| -> Vec<(u32, u32)> {
+            frakt
+                .pixels
+                .iter()
+                .map(|pixel| (pixel.zn.to_bits(), pixel.count.to_bits()))
+                .collect()
+        };
+        assert_eq!(bits(&decoded), bits(&frakt));
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        let bytes = frakt().to_bytes().unwrap();
+        assert_eq!(Frakt::from_bytes(b"PNG").unwrap_err(), "Not a .frakt file");
+
+        let mut version = bytes.clone();
+        version[MAGIC.len()] = 2;
+        assert_eq!(
+            Frakt::from_bytes(&version).unwrap_err(),
+            "Unsupported .frakt version 2"
+        );
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(Frakt::from_bytes(truncated)
+            .unwrap_err()
+            .ends_with("47 bytes of pixels instead of 48 for 3x2"));
+
+        let mut scene_size = bytes;
+        scene_size[MAGIC.len() + 1] = 0xFF;
+        assert_eq!(
+            Frakt::from_bytes(&scene_size).unwrap_err(),
+            "Truncated .frakt scene"
+        );
+    }
+}
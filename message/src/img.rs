@@ -4,8 +4,8 @@ use image::{ImageBuffer, Rgb};
 /// Save the fractal image to a file
 #[allow(dead_code)]
 pub fn save_fractal_image(pixels: Vec<u8>, resolution: Resolution, filename: &str) {
-    let width = resolution.nx.clone() as u32;
-    let height = resolution.ny.clone() as u32;
+    let width = resolution.nx as u32;
+    let height = resolution.ny as u32;
     let mut imgbuf = ImageBuffer::new(width, height);
 
     for (x, y, pixel) in imgbuf.enumerate_pixels_mut() {
@@ -1,4 +1,5 @@
-use crate::message::{IteratedSinZ, PixelIntensity, Range, Resolution};
+use crate::message::{IteratedSinZ, PixelIntensity, Plane, Range, Resolution};
+use crate::plane::PlaneFractal;
 use complex::complex::Complex;
 
 impl IteratedSinZ {
@@ -63,3 +64,16 @@ impl IteratedSinZ {
         (z0.norm_squared(), iteration_count as f64)
     }
 }
+
+/// z_{n+1} = sin(z_n) * c, drawn by default in its dynamic plane
+impl PlaneFractal for IteratedSinZ {
+    fn default_plane(&self) -> Plane {
+        Plane::Dynamic { c: self.c }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        let (final_z, iteration_count) =
+            IteratedSinZ { c }.calculate_escape_time_and_iterations(z0, max_iteration);
+        (final_z / 50.0, iteration_count / max_iteration as f64)
+    }
+}
@@ -1,10 +1,32 @@
+pub mod animation;
+mod build_buddhabrot;
+mod build_burning_ship;
+mod build_celtic;
+mod build_custom_formula;
 mod build_fractale;
 mod build_julia;
+mod build_lyapunov;
+mod build_magnet;
 mod build_mandelbrot;
+mod build_multibrot;
+mod build_phoenix;
+mod build_tricorn;
+pub mod color_map;
 pub mod drawing_image;
+mod escape_time;
+pub mod export;
+mod formula;
+pub mod frakt;
 mod img;
 mod iterated_sin_z;
 pub mod message;
+mod newton_polynomial;
 mod newton_raphson_z;
 mod nova_newton_raphson;
+mod orbit_trap;
+mod perturbation;
+mod plane;
+pub mod png_metadata;
+pub mod scene;
 pub mod send_message;
+pub mod terminal;
@@ -1,4 +1,5 @@
 use complex::complex::Complex;
+use complex::complex_dd::ComplexDD;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
@@ -46,6 +47,12 @@ pub struct IteratedSinZ {
 pub struct JuliaDescriptor {
     pub c: Complex,
     pub divergence_threshold_square: f64,
+    /// Output the distance to the set in pixels in `zn`, instead of the modulus
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub distance_estimation: bool,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
 }
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct NewtonRaphsonZ3 {}
@@ -53,6 +60,16 @@ pub struct NewtonRaphsonZ3 {}
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Copy)]
 pub struct NewtonRaphsonZ4 {}
 
+/// Newton fractal of an arbitrary polynomial p, iterating z - relaxation * p(z) / p'(z).
+/// A point has converged when it is closer than `tolerance` to a root of p.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NewtonPolynomial {
+    /// Coefficients of p from the constant term up
+    pub coefficients: Vec<Complex>,
+    pub relaxation: Complex,
+    pub tolerance: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct NovaNewtonRaphsonZ3 {}
 
@@ -60,15 +77,158 @@ pub struct NovaNewtonRaphsonZ3 {}
 pub struct NovaNewtonRaphsonZ4 {}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
-pub struct Mandelbrot {}
+pub struct Mandelbrot {
+    /// Output the distance to the set in pixels in `zn`, instead of the modulus
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub distance_estimation: bool,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Multibrot set, z^power + c with z_0 = 0 and c the pixel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Multibrot {
+    pub power: f64,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Julia set of z^power + c, with z_0 the pixel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Multijulia {
+    pub c: Complex,
+    pub power: f64,
+    pub divergence_threshold_square: f64,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Phoenix fractal, z_{n+1} = z_n^2 + c + p * z_{n-1} with z_0 the pixel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Phoenix {
+    pub c: Complex,
+    pub p: Complex,
+    pub divergence_threshold_square: f64,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Lyapunov fractal of the logistic map, with the rates `a` and `b` taken along the axes
+/// and applied in the order of `sequence` (for example "AB" or "AABAB")
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Lyapunov {
+    pub sequence: String,
+    pub warmup: u16,
+}
+
+/// Buddhabrot: density of the escaping Mandelbrot orbits of `samples` random points,
+/// keeping only the orbits longer than `min_iteration`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Buddhabrot {
+    pub min_iteration: u16,
+    pub samples: u32,
+}
+
+/// Magnet Type I, z_{n+1} = ((z_n^2 + c - 1) / (2 z_n + c - 2))^2 with z_0 = 0 and c the pixel.
+/// The iteration stops on escape or on convergence to 1.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct MagnetI {
+    pub divergence_threshold_square: f64,
+    pub convergence_epsilon: f64,
+}
+
+/// Magnet Type II, the cubic renormalisation map, with z_0 = 0 and c the pixel.
+/// The iteration stops on escape or on convergence to 1.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct MagnetII {
+    pub divergence_threshold_square: f64,
+    pub convergence_epsilon: f64,
+}
+
+/// Fractal of a formula written by the user, such as `z^3 + c*sin(z)`.
+/// `z0` is an expression too, usually `0` for the parameter plane or `pixel` for a Julia set.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomFormula {
+    pub expression: String,
+    pub z0: String,
+    pub bailout: f64,
+    pub convergence_epsilon: f64,
+}
+
+/// Burning Ship, in Julia mode when `c` is set
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct BurningShip {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c: Option<Complex>,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Tricorn (Mandelbar), in Julia mode when `c` is set
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Tricorn {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c: Option<Complex>,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Celtic Mandelbrot, in Julia mode when `c` is set
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Celtic {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c: Option<Complex>,
+    /// Colour by the smallest distance from the orbit to this trap, stored in `zn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit_trap: Option<OrbitTrap>,
+}
+
+/// Shape the orbit of a pixel is compared to for orbit-trap colouring
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum OrbitTrap {
+    Point {
+        center: Complex,
+    },
+    /// Line through `point` making the angle `angle` (in radians) with the real axis
+    Line {
+        point: Complex,
+        angle: f64,
+    },
+    Circle {
+        center: Complex,
+        radius: f64,
+    },
+    /// Horizontal and vertical lines crossing at `center`
+    Cross {
+        center: Complex,
+    },
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum FractalDescriptor {
     IteratedSinZ(IteratedSinZ),
     Julia(JuliaDescriptor),
     Mandelbrot(Mandelbrot),
+    Multibrot(Multibrot),
+    Multijulia(Multijulia),
+    Phoenix(Phoenix),
+    Lyapunov(Lyapunov),
+    Buddhabrot(Buddhabrot),
+    MagnetI(MagnetI),
+    MagnetII(MagnetII),
+    CustomFormula(CustomFormula),
+    BurningShip(BurningShip),
+    Tricorn(Tricorn),
+    Celtic(Celtic),
     NewtonRaphsonZ3(NewtonRaphsonZ3),
     NewtonRaphsonZ4(NewtonRaphsonZ4),
+    NewtonPolynomial(NewtonPolynomial),
     NovaNewtonRaphsonZ3(NovaNewtonRaphsonZ3),
     NovaNewtonRaphsonZ4(NovaNewtonRaphsonZ4),
 }
@@ -79,6 +239,28 @@ pub struct FragmentRequest {
     pub maximal_work_load: u32,
 }
 
+/// Plane of the pixels, for the fractals iterating z_{n+1} = f(z_n, c)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Plane {
+    /// The pixel is `c` and the orbit starts at `z0`, as for Mandelbrot
+    Parameter { z0: Complex },
+    /// The pixel is `z_0` and `c` is fixed, as for Julia
+    Dynamic { c: Complex },
+}
+
+/// Samples taken in each pixel to smooth the edges of the fractal, whose intensities are
+/// averaged before the pixel is coloured
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Supersampling {
+    /// `n` x `n` samples on a regular grid over the pixel
+    Grid { n: u8 },
+    /// `n` x `n` samples, each at a random position in its cell of the grid
+    Jittered { n: u8 },
+    /// One sample per pixel, then `n` x `n` samples on a grid for the pixels whose `count`
+    /// differs from the one of a neighbour by more than `threshold`
+    Adaptive { n: u8, threshold: f32 },
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct FragmentTask {
     pub id: U8Data,
@@ -86,6 +268,15 @@ pub struct FragmentTask {
     pub resolution: Resolution,
     pub range: Range,
     pub fractal: FractalDescriptor,
+    /// Plane to draw the fractal in, instead of its own one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plane: Option<Plane>,
+    /// Samples per pixel, a single one when it is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersampling: Option<Supersampling>,
+    /// High precision centre of the scene. When it is set, `range` is relative to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<ComplexDD>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -96,9 +287,18 @@ pub struct FragmentResult {
     pub pixels: PixelData,
 }
 
+/// Sent by a worker instead of a result when the fragment task cannot be calculated,
+/// for example because of a formula that does not parse
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FragmentError {
+    pub id: U8Data,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Message {
     FragmentTask(FragmentTask),
     FragmentResult(FragmentResult),
     FragmentRequest(FragmentRequest),
+    FragmentError(FragmentError),
 }
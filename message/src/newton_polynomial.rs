@@ -0,0 +1,216 @@
+use complex::complex::Complex;
+use complex::polynomial::Polynomial;
+
+use crate::message::{NewtonPolynomial, PixelIntensity, Range, Resolution};
+
+/// Maximum number of iterations to find the roots of the polynomial
+const ROOTS_MAX_ITERATION: usize = 500;
+
+/// The roots of a polynomial, to the precision of f64 relative to their modulus
+fn find_roots(polynomial: &Polynomial) -> Vec<Complex> {
+    polynomial.roots(ROOTS_MAX_ITERATION, f64::EPSILON)
+}
+
+impl NewtonPolynomial {
+    /// Check that the polynomial has roots and that the iteration can move towards them
+    pub fn validate(&self) -> Result<(), String> {
+        let finite = |z: &Complex| z.re.is_finite() && z.im.is_finite();
+        if !self.coefficients.iter().all(finite) {
+            return Err("The coefficients of the polynomial must be finite".to_string());
+        }
+        if Polynomial::new(self.coefficients.clone()).degree() < 1 {
+            return Err("The polynomial must be of degree 1 or more".to_string());
+        }
+        if !(self.tolerance.is_finite() && self.tolerance > 0.0) {
+            return Err(format!(
+                "The tolerance must be positive and finite, not {}",
+                self.tolerance
+            ));
+        }
+        if !finite(&self.relaxation) || self.relaxation.norm() == 0.0 {
+            return Err(format!(
+                "The relaxation must be finite and not zero, not {}",
+                self.relaxation
+            ));
+        }
+        Ok(())
+    }
+
+    /// Calculate the Newton fractal of the polynomial.
+    /// `zn` holds which root the point converged to: `index / number_of_roots` for the root at
+    /// `index`. A point reaching no root has a `count` of 1, whatever the iteration it stopped
+    /// at, which the basin colouring draws in black.
+    pub fn calculate_fractal_newton_polynomial(
+        &self,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        let polynomial = Polynomial::new(self.coefficients.clone());
+        let roots = find_roots(&polynomial);
+
+        let width = resolution.nx;
+        let height = resolution.ny;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let x_frac = x as f64 / width as f64 * (range.max.x - range.min.x) + range.min.x;
+                let y_frac = y as f64 / height as f64 * (range.max.y - range.min.y) + range.min.y;
+
+                let (root, count) = self.compute_pixel(
+                    Complex::new(x_frac, y_frac),
+                    &polynomial,
+                    &roots,
+                    max_iteration,
+                );
+                pixels.push(match root {
+                    Some(index) => PixelIntensity {
+                        zn: index as f32 / roots.len() as f32,
+                        count: (count / max_iteration as f64) as f32,
+                    },
+                    None => PixelIntensity {
+                        zn: 0.0,
+                        count: 1.0,
+                    },
+                });
+            }
+        }
+        pixels
+    }
+
+    /// Iterate the relaxed Newton method from `zn`
+    /// and return the index of the root reached and the number of iterations
+    fn compute_pixel(
+        &self,
+        mut zn: Complex,
+        polynomial: &Polynomial,
+        roots: &[Complex],
+        max_iteration: u16,
+    ) -> (Option<usize>, f64) {
+        for count in 0..max_iteration {
+            if let Some(index) = roots
+                .iter()
+                .position(|root| (zn - *root).norm() < self.tolerance)
+            {
+                return (Some(index), count as f64);
+            }
+            let (value, derivative) = polynomial.eval_with_derivative(zn);
+            zn -= self.relaxation * value / derivative;
+            if zn.is_nan() || zn.is_infinite() {
+                return (None, count as f64);
+            }
+        }
+        (None, max_iteration as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use complex::complex::Complex;
+    use complex::polynomial::Polynomial;
+
+    use super::find_roots;
+    use crate::message::{NewtonPolynomial, Point, Range, Resolution};
+
+    #[test]
+    fn test_every_root_is_found_and_reached() {
+        let expected = [
+            Complex::new(120.0, -3.5),
+            Complex::new(-7.25, 0.0),
+            Complex::new(0.5, 2.0),
+            Complex::new(0.5, -2.0),
+        ];
+        let polynomial = Polynomial::from_roots(&expected);
+        let newton = NewtonPolynomial {
+            coefficients: polynomial.coefficients.clone(),
+            relaxation: Complex::new(1.0, 0.0),
+            tolerance: 1e-6,
+        };
+        let roots = find_roots(&polynomial);
+        assert_eq!(roots.len(), expected.len());
+        for root in expected {
+            let index = roots
+                .iter()
+                .position(|found| (*found - root).norm() < 1e-9 * root.norm())
+                .unwrap_or_else(|| panic!("{root} not in {roots:?}"));
+            let start = root + Complex::new(1e-3, 1e-3);
+            let (reached, _) = newton.compute_pixel(start, &polynomial, &roots, 64);
+            assert_eq!(reached, Some(index));
+        }
+    }
+
+    #[test]
+    fn test_basins_of_every_root_are_distinct_from_no_root() {
+        // z^3 - 1, whose derivative vanishes at the origin
+        let newton = NewtonPolynomial {
+            coefficients: vec![
+                Complex::new(-1.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(1.0, 0.0),
+            ],
+            relaxation: Complex::new(1.0, 0.0),
+            tolerance: 1e-6,
+        };
+        let range = Range {
+            min: Point { x: 0.0, y: 0.0 },
+            max: Point { x: 1.0, y: 1.0 },
+        };
+        let pixels =
+            newton.calculate_fractal_newton_polynomial(64, Resolution { nx: 1, ny: 1 }, range);
+        assert_eq!(pixels[0].count, 1.0);
+
+        let polynomial = Polynomial::new(newton.coefficients.clone());
+        let roots = find_roots(&polynomial);
+        let mut zns: Vec<f32> = roots
+            .iter()
+            .map(|root| {
+                let range = Range {
+                    min: Point {
+                        x: root.re + 1e-3,
+                        y: root.im,
+                    },
+                    max: Point {
+                        x: root.re + 1.0,
+                        y: root.im + 1.0,
+                    },
+                };
+                let pixel = newton.calculate_fractal_newton_polynomial(
+                    64,
+                    Resolution { nx: 1, ny: 1 },
+                    range,
+                );
+                assert!(pixel[0].count < 1.0);
+                pixel[0].zn
+            })
+            .collect();
+        zns.sort_by(f32::total_cmp);
+        assert_eq!(zns, [0.0, 1.0 / 3.0, 2.0 / 3.0]);
+    }
+
+    #[test]
+    fn test_invalid_polynomials_are_rejected() {
+        let newton = |coefficients: Vec<Complex>, relaxation: Complex, tolerance: f64| {
+            NewtonPolynomial {
+                coefficients,
+                relaxation,
+                tolerance,
+            }
+            .validate()
+        };
+        let one = Complex::new(1.0, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+        assert!(newton(vec![one, one], one, 1e-6).is_ok());
+        assert_eq!(
+            newton(vec![], one, 1e-6).unwrap_err(),
+            "The polynomial must be of degree 1 or more"
+        );
+        assert!(newton(vec![one, zero, zero], one, 1e-6).is_err());
+        assert!(newton(vec![one, Complex::new(f64::NAN, 0.0)], one, 1e-6).is_err());
+        assert!(newton(vec![one, one], one, 0.0).is_err());
+        assert!(newton(vec![one, one], one, f64::INFINITY).is_err());
+        assert!(newton(vec![one, one], zero, 1e-6).is_err());
+        assert!(newton(vec![one, one], Complex::new(f64::NAN, 0.0), 1e-6).is_err());
+    }
+}
@@ -88,7 +88,6 @@ impl NewtonRaphsonZ4 {
     /// Compute the pixel using the Newton-Raphson method for z^4
     /// and return the pixel intensity
     /// and the number of iterations
-
     fn compute_pixel_z4(&self, mut zn: Complex, max_iteration: u16) -> (Complex, f64) {
         let mut count = 0;
         let epsilon = 0.000001;
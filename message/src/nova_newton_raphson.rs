@@ -1,5 +1,45 @@
-use crate::message::{NovaNewtonRaphsonZ3, NovaNewtonRaphsonZ4, PixelIntensity, Range, Resolution};
+use crate::message::{
+    NovaNewtonRaphsonZ3, NovaNewtonRaphsonZ4, PixelIntensity, Plane, Range, Resolution,
+};
+use crate::plane::PlaneFractal;
 use complex::complex::Complex;
+use complex::polynomial::Polynomial;
+use std::f64::consts::TAU;
+
+/// Maximum number of iterations to find the fixed points of the iteration
+const FIXED_POINTS_MAX_ITERATION: usize = 100;
+
+/// The fixed points of z - (z^degree - 1) / (degree z^(degree-1)) + c, which are the roots of
+/// z^degree - degree c z^(degree-1) - 1, sorted by their argument in [0, 2π[.
+/// For c = 0 they are the roots of unity, and they move with c.
+fn fixed_points(c: Complex, degree: u32) -> Vec<Complex> {
+    let degree = degree as usize;
+    let mut coefficients = vec![Complex::new(0.0, 0.0); degree + 1];
+    coefficients[0] = Complex::new(-1.0, 0.0);
+    coefficients[degree - 1] = -(c * degree as f64);
+    coefficients[degree] = Complex::new(1.0, 0.0);
+    let mut roots = Polynomial::new(coefficients).roots(FIXED_POINTS_MAX_ITERATION, f64::EPSILON);
+    roots.sort_by(|a, b| a.arg().rem_euclid(TAU).total_cmp(&b.arg().rem_euclid(TAU)));
+    roots
+}
+
+/// `zn` and `count` of a point whose orbit for the parameter `c` ended at `z` after `count`
+/// iterations. A Nova fractal of degree `degree` has as many fixed points for every `c`:
+/// `zn` is the rank by argument of the one nearest to `z`, divided by `degree`, in [0, 1[.
+/// In the parameter plane every pixel has its own fixed points, so the hue tells which of them
+/// was reached rather than a basin shared with the neighbouring pixels.
+/// An orbit that did not converge has a `count` of 1, which the basin colouring draws in black.
+fn basin(z: Complex, c: Complex, count: f64, degree: u32, max_iteration: u16) -> (f64, f64) {
+    if !z.norm_squared().is_finite() {
+        return (0.0, 1.0);
+    }
+    let index = fixed_points(c, degree)
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (z - **a).norm().total_cmp(&(z - **b).norm()))
+        .map_or(0, |(index, _)| index);
+    (index as f64 / degree as f64, count / max_iteration as f64)
+}
 
 impl NovaNewtonRaphsonZ3 {
     /// Calculate the fractal using the Nova Newton Raphson Z3 algorithm
@@ -23,14 +63,11 @@ impl NovaNewtonRaphsonZ3 {
                 let initial_z = Complex::new(1.0, 0.0);
                 let c = Complex::new(x_frac, y_frac);
 
-                let (_, count) = self.iterate_nova_newton_raphson_z3(initial_z, c, max_iteration);
-
-                let intensity = PixelIntensity {
-                    zn: 0.0,
-                    count: (count / max_iteration as f64) as f32,
-                };
-
-                pixels.push(intensity);
+                let (zn, count) = self.orbit(initial_z, c, max_iteration);
+                pixels.push(PixelIntensity {
+                    zn: zn as f32,
+                    count: count as f32,
+                });
             }
         }
 
@@ -86,14 +123,11 @@ impl NovaNewtonRaphsonZ4 {
                 let initial_z = Complex::new(1.0, 0.0); // Utilisez 1.0 + 0.0i comme z_0
                 let c = Complex::new(x_frac, y_frac);
 
-                let (_, count) = self.iterate_nova_newton_raphson_z4(initial_z, c, max_iteration);
-
-                let intensity = PixelIntensity {
-                    zn: 0.0,
-                    count: (count / max_iteration as f64) as f32,
-                };
-
-                pixels.push(intensity);
+                let (zn, count) = self.orbit(initial_z, c, max_iteration);
+                pixels.push(PixelIntensity {
+                    zn: zn as f32,
+                    count: count as f32,
+                });
             }
         }
 
@@ -125,3 +159,87 @@ impl NovaNewtonRaphsonZ4 {
         (zn, count as f64)
     }
 }
+
+/// Nova fractals are drawn by default in their parameter plane, starting from z_0 = 1
+impl PlaneFractal for NovaNewtonRaphsonZ3 {
+    fn default_plane(&self) -> Plane {
+        Plane::Parameter {
+            z0: Complex::new(1.0, 0.0),
+        }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        let (zn, count) = self.iterate_nova_newton_raphson_z3(z0, c, max_iteration);
+        basin(zn, c, count, 3, max_iteration)
+    }
+}
+
+impl PlaneFractal for NovaNewtonRaphsonZ4 {
+    fn default_plane(&self) -> Plane {
+        Plane::Parameter {
+            z0: Complex::new(1.0, 0.0),
+        }
+    }
+
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64) {
+        let (zn, count) = self.iterate_nova_newton_raphson_z4(z0, c, max_iteration);
+        basin(zn, c, count, 4, max_iteration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use complex::complex::Complex;
+
+    use super::{basin, fixed_points};
+    use crate::message::NovaNewtonRaphsonZ3;
+
+    #[test]
+    fn test_attractors_have_distinct_indices() {
+        let zero = Complex::new(0.0, 0.0);
+        let zns: Vec<f64> = (0..4)
+            .map(|k| {
+                basin(
+                    Complex::from_polar(1.1, TAU * k as f64 / 4.0 + 0.1),
+                    zero,
+                    3.0,
+                    4,
+                    10,
+                )
+                .0
+            })
+            .collect();
+        assert_eq!(zns, [0.0, 0.25, 0.5, 0.75]);
+        assert_eq!(
+            basin(Complex::new(1.0, -1e-9), zero, 5.0, 3, 10),
+            (0.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn test_fixed_points_move_with_c() {
+        let c = Complex::new(0.3, 0.2);
+        let points = fixed_points(c, 3);
+        assert_eq!(points.len(), 3);
+        for (index, &point) in points.iter().enumerate() {
+            let next = point - (point.cube() - 1.0) / (point.square() * 3.0) + c;
+            assert!((next - point).norm() < 1e-12, "{point} is not fixed");
+            let (zn, _) = basin(point + Complex::new(1e-4, 0.0), c, 3.0, 3, 10);
+            assert_eq!(zn, index as f64 / 3.0);
+        }
+
+        // the limit of an orbit is one of them
+        let (limit, count) =
+            NovaNewtonRaphsonZ3 {}.iterate_nova_newton_raphson_z3(Complex::new(1.0, 0.0), c, 100);
+        assert!(count < 100.0);
+        assert!(points.iter().any(|point| (limit - *point).norm() < 1e-2));
+    }
+
+    #[test]
+    fn test_diverging_orbit_is_not_a_basin() {
+        let z = Complex::new(f64::NAN, 0.0);
+        assert_eq!(basin(z, Complex::new(0.0, 0.0), 2.0, 3, 10).1, 1.0);
+    }
+}
@@ -0,0 +1,51 @@
+use complex::complex::Complex;
+
+use crate::message::OrbitTrap;
+
+impl OrbitTrap {
+    /// Distance from `z` to the trap
+    pub fn distance(&self, z: Complex) -> f64 {
+        match *self {
+            OrbitTrap::Point { center } => (z - center).norm(),
+            OrbitTrap::Line { point, angle } => {
+                ((z - point) * Complex::from_polar(1.0, -angle)).im.abs()
+            }
+            OrbitTrap::Circle { center, radius } => ((z - center).norm() - radius).abs(),
+            OrbitTrap::Cross { center } => {
+                let offset = z - center;
+                offset.re.abs().min(offset.im.abs())
+            }
+        }
+    }
+}
+
+/// Smallest distance from the points of an orbit to an optional trap
+pub(crate) struct TrapDistance {
+    trap: Option<OrbitTrap>,
+    distance: f64,
+}
+
+impl TrapDistance {
+    pub(crate) fn new(trap: Option<OrbitTrap>) -> TrapDistance {
+        TrapDistance {
+            trap,
+            distance: f64::INFINITY,
+        }
+    }
+
+    /// Take a new point of the orbit into account
+    pub(crate) fn visit(&mut self, z: Complex) {
+        if let Some(trap) = self.trap {
+            self.distance = self.distance.min(trap.distance(z));
+        }
+    }
+
+    /// The value to store in `zn`: the distance to the trap when there is one and a point was
+    /// visited, else `zn` (for the points escaping right away)
+    pub(crate) fn zn(&self, zn: f64) -> f64 {
+        match self.trap {
+            Some(_) if self.distance.is_finite() => self.distance,
+            _ => zn,
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use complex::complex::Complex;
+use complex::complex_dd::ComplexDD;
+
+use crate::message::{PixelIntensity, Plane, Range, Resolution, Supersampling};
+use crate::plane::{rasterize, rasterize_supersampled, PlaneFractal};
+
+/// Square of the radius past which the reference orbit is not followed any further, above the
+/// escape radius of the distance estimation
+const REFERENCE_BAILOUT_SQUARE: f64 = 1e12;
+
+/// Orbit of z^2 + c at the centre of a deep zoom, iterated in double-double and rounded to f64
+/// afterwards: the pixels only follow their small offset from it, which f64 holds at any depth.
+pub(crate) struct ReferenceOrbit {
+    plane: Plane,
+    points: Vec<Complex>,
+}
+
+impl ReferenceOrbit {
+    /// Iterate the orbit of the centre in the given plane, until it escapes or `max_iteration`
+    pub fn new(plane: Plane, center: ComplexDD, max_iteration: u16) -> ReferenceOrbit {
+        let (mut z, c) = match plane {
+            Plane::Parameter { z0 } => (ComplexDD::from(z0), center),
+            Plane::Dynamic { c } => (center, ComplexDD::from(c)),
+        };
+        let mut points = vec![z.to_complex()];
+        // at least two points, so that an orbit always has a next reference point
+        for _ in 0..max_iteration.max(1) {
+            z = z.square().add(c);
+            let point = z.to_complex();
+            points.push(point);
+            if !point.norm_squared().is_finite() || point.norm_squared() > REFERENCE_BAILOUT_SQUARE
+            {
+                break;
+            }
+        }
+        ReferenceOrbit { plane, points }
+    }
+
+    /// The orbit of the pixel at `offset` from the centre
+    pub fn perturbed(&self, offset: Complex) -> PerturbedOrbit<'_> {
+        let (dz, dc) = self.plane.derivatives();
+        PerturbedOrbit {
+            points: &self.points,
+            index: 0,
+            delta: offset * dz,
+            delta_c: offset * dc,
+        }
+    }
+}
+
+/// Orbit of a pixel as its difference `delta` with the reference orbit, iterated as
+/// delta_{n+1} = 2 Z_n delta_n + delta_n^2 + delta_c. When the pixel gets closer to 0 than to
+/// the reference, or the reference ends, the pixel is rebased on the start of the reference.
+pub(crate) struct PerturbedOrbit<'a> {
+    points: &'a [Complex],
+    index: usize,
+    delta: Complex,
+    delta_c: Complex,
+}
+
+impl PerturbedOrbit<'_> {
+    /// First point of the orbit of the pixel
+    pub fn start(&self) -> Complex {
+        self.points[0] + self.delta
+    }
+
+    /// Next point of the orbit of the pixel
+    pub fn next(&mut self) -> Complex {
+        let reference = self.points[self.index];
+        self.delta = self.delta * (reference * 2.0 + self.delta) + self.delta_c;
+        self.index += 1;
+        let z = self.points[self.index] + self.delta;
+        if self.index == self.points.len() - 1 || z.norm_squared() < self.delta.norm_squared() {
+            self.delta = z - self.points[0];
+            self.index = 0;
+        }
+        z
+    }
+}
+
+/// Fractal iterating z^2 + c, whose deep zooms are calculated by perturbation of the orbit of
+/// their high precision centre
+pub(crate) trait Perturbation: PlaneFractal {
+    /// Calculate a pixel of the given plane from the start `z0` of its orbit, whose next points
+    /// are given by `next` from the current one
+    fn follow_orbit(
+        &self,
+        plane: Plane,
+        z0: Complex,
+        next: impl FnMut(Complex) -> Complex,
+        pixel_size: f64,
+        max_iteration: u16,
+    ) -> (f64, f64);
+
+    /// Calculate a fragment whose range is relative to `center`, supersampled when asked
+    fn calculate_deep(
+        &self,
+        plane: Plane,
+        center: ComplexDD,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+        supersampling: Option<Supersampling>,
+    ) -> Vec<PixelIntensity> {
+        let reference = ReferenceOrbit::new(plane, center, max_iteration);
+        let sample = |offset: Complex, pixel_size: f64| {
+            let mut orbit = reference.perturbed(offset);
+            let z0 = orbit.start();
+            self.follow_orbit(plane, z0, |_| orbit.next(), pixel_size, max_iteration)
+        };
+        match supersampling {
+            Some(supersampling) => rasterize_supersampled(resolution, range, supersampling, sample),
+            None => rasterize(resolution, range, sample),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use complex::complex::Complex;
+    use complex::complex_dd::ComplexDD;
+
+    use super::ReferenceOrbit;
+    use crate::message::Plane;
+
+    /// Orbit of z^2 + c iterated directly in f64
+    fn direct_orbit(mut z: Complex, c: Complex, steps: usize) -> Vec<Complex> {
+        let mut points = vec![z];
+        for _ in 0..steps {
+            z = z.square() + c;
+            points.push(z);
+        }
+        points
+    }
+
+    #[test]
+    fn test_perturbed_orbit_matches_direct_orbit() {
+        let center = Complex::new(-0.75, 0.1);
+        let offset = Complex::new(1e-3, -2e-3);
+        let plane = Plane::Parameter {
+            z0: Complex::new(0.0, 0.0),
+        };
+        let reference = ReferenceOrbit::new(plane, ComplexDD::from(center), 50);
+        let mut orbit = reference.perturbed(offset);
+        let direct = direct_orbit(Complex::new(0.0, 0.0), center + offset, 50);
+        assert_eq!(orbit.start(), direct[0]);
+        for point in &direct[1..] {
+            if point.norm_squared() > 4.0 {
+                break;
+            }
+            assert!((orbit.next() - *point).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_perturbed_orbit_in_dynamic_plane() {
+        let c = Complex::new(0.285, 0.013);
+        let center = Complex::new(0.1, -0.2);
+        let offset = Complex::new(-3e-4, 5e-4);
+        let reference = ReferenceOrbit::new(Plane::Dynamic { c }, ComplexDD::from(center), 30);
+        let mut orbit = reference.perturbed(offset);
+        let direct = direct_orbit(center + offset, c, 30);
+        assert!((orbit.start() - direct[0]).norm() < 1e-15);
+        for point in &direct[1..] {
+            assert!((orbit.next() - *point).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_offset_keeps_precision_beyond_f64() {
+        // a pixel 1e-20 away from a centre with more digits than an f64, whose difference with
+        // the centre is followed in double-double as the expected value
+        let center: ComplexDD = serde_json::from_str(
+            r#"{"re": "-0.7436438870371587047521915061148", "im": "0.1318259042053119704931320563851"}"#,
+        )
+        .unwrap();
+        let offset = Complex::new(1e-20, 0.0);
+        let plane = Plane::Parameter {
+            z0: Complex::new(0.0, 0.0),
+        };
+        let reference = ReferenceOrbit::new(plane, center, 100);
+        let mut orbit = reference.perturbed(offset);
+        let pixel = center + ComplexDD::from(offset);
+        let (mut z_center, mut z_pixel) = (ComplexDD::default(), ComplexDD::default());
+        for _ in 0..60 {
+            z_center = z_center.square().add(center);
+            z_pixel = z_pixel.square().add(pixel);
+            orbit.next();
+            let expected = (z_pixel - z_center).to_complex();
+            assert!(expected.norm() > 0.0);
+            assert!((orbit.delta - expected).norm() <= 1e-6 * expected.norm());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use complex::complex::Complex;
+use rand::Rng;
+
+use crate::message::{PixelIntensity, Plane, Range, Resolution, Supersampling};
+
+/// Largest number of samples per side of a pixel, 256 samples per pixel
+const MAX_SAMPLES_PER_SIDE: u8 = 16;
+
+impl Supersampling {
+    /// Check that there is at least one and at most 16 samples per side of a pixel, and that
+    /// the threshold of the adaptive mode is not negative
+    pub fn validate(&self) -> Result<(), String> {
+        let (n, threshold) = match *self {
+            Supersampling::Grid { n } | Supersampling::Jittered { n } => (n, 0.0),
+            Supersampling::Adaptive { n, threshold } => (n, threshold),
+        };
+        if !(1..=MAX_SAMPLES_PER_SIDE).contains(&n) {
+            return Err(format!(
+                "{n} samples per side of a pixel is not between 1 and {MAX_SAMPLES_PER_SIDE}"
+            ));
+        }
+        if threshold.is_nan() || threshold < 0.0 {
+            return Err(format!("The threshold {threshold} is not positive"));
+        }
+        Ok(())
+    }
+}
+
+impl Plane {
+    /// Starting point and parameter of the orbit of a pixel
+    pub fn start(&self, pixel: Complex) -> (Complex, Complex) {
+        match *self {
+            Plane::Parameter { z0 } => (z0, pixel),
+            Plane::Dynamic { c } => (pixel, c),
+        }
+    }
+
+    /// Derivatives of the starting point and of the parameter with respect to the pixel
+    pub fn derivatives(&self) -> (Complex, Complex) {
+        let (zero, one) = (Complex::new(0.0, 0.0), Complex::new(1.0, 0.0));
+        match self {
+            Plane::Parameter { .. } => (zero, one),
+            Plane::Dynamic { .. } => (one, zero),
+        }
+    }
+}
+
+/// Fractal iterating z_{n+1} = f(z_n, c), which can be drawn either in the parameter plane
+/// (the pixel is `c`) or in the dynamic plane (the pixel is `z_0`).
+pub(crate) trait PlaneFractal {
+    /// The plane the fractal is drawn in when the task does not choose one
+    fn default_plane(&self) -> Plane;
+
+    /// Iterate from `z0` with the parameter `c` and return the `zn` and `count` of the pixel
+    fn orbit(&self, z0: Complex, c: Complex, max_iteration: u16) -> (f64, f64);
+
+    /// Calculate a pixel of width `pixel_size` in the given plane
+    fn calculate_pixel(
+        &self,
+        plane: Plane,
+        pixel: Complex,
+        _pixel_size: f64,
+        max_iteration: u16,
+    ) -> (f64, f64) {
+        let (z0, c) = plane.start(pixel);
+        self.orbit(z0, c, max_iteration)
+    }
+
+    /// Calculate the fractal in the given plane for a given resolution and range.
+    fn calculate_in_plane(
+        &self,
+        plane: Plane,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+    ) -> Vec<PixelIntensity> {
+        rasterize(resolution, range, |pixel, pixel_size| {
+            self.calculate_pixel(plane, pixel, pixel_size, max_iteration)
+        })
+    }
+
+    /// Calculate the fractal in the given plane with several samples per pixel, averaging their
+    /// `zn` and `count`
+    fn calculate_supersampled(
+        &self,
+        plane: Plane,
+        max_iteration: u16,
+        resolution: Resolution,
+        range: Range,
+        supersampling: Supersampling,
+    ) -> Vec<PixelIntensity> {
+        rasterize_supersampled(resolution, range, supersampling, |pixel, pixel_size| {
+            self.calculate_pixel(plane, pixel, pixel_size, max_iteration)
+        })
+    }
+}
+
+/// Calculate every pixel of a fragment with `sample`, given the coordinates of the pixel and
+/// its width
+pub(crate) fn rasterize(
+    resolution: Resolution,
+    range: Range,
+    sample: impl Fn(Complex, f64) -> (f64, f64),
+) -> Vec<PixelIntensity> {
+    let width = resolution.nx;
+    let height = resolution.ny;
+    let pixel_size = (range.max.x - range.min.x) / width as f64;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let x_frac = x as f64 / width as f64 * (range.max.x - range.min.x) + range.min.x;
+            let y_frac = y as f64 / height as f64 * (range.max.y - range.min.y) + range.min.y;
+
+            let (zn, count) = sample(Complex::new(x_frac, y_frac), pixel_size);
+            pixels.push(PixelIntensity {
+                zn: zn as f32,
+                count: count as f32,
+            });
+        }
+    }
+    pixels
+}
+
+/// Calculate every pixel of a fragment with several samples of `sample`, averaging their `zn`
+/// and `count`. The samples of a pixel cover the square of one pixel centred on the point a
+/// single sample would be taken at, so that the image does not move, and the distance
+/// estimation keeps measuring in pixels of the image.
+pub(crate) fn rasterize_supersampled(
+    resolution: Resolution,
+    range: Range,
+    supersampling: Supersampling,
+    sample: impl Fn(Complex, f64) -> (f64, f64),
+) -> Vec<PixelIntensity> {
+    let width = resolution.nx as i64;
+    let height = resolution.ny as i64;
+    let pixel_width = (range.max.x - range.min.x) / width as f64;
+    let pixel_height = (range.max.y - range.min.y) / height as f64;
+    let mut rng = rand::thread_rng();
+
+    // sample at a position in pixels, which may be fractional or outside of the fragment
+    let sample_at = |x: f64, y: f64| {
+        let pixel = Complex::new(
+            range.min.x + x * pixel_width,
+            range.min.y + y * pixel_height,
+        );
+        sample(pixel, pixel_width)
+    };
+    let mut average = |x: i64, y: i64, n: u8, jittered: bool| {
+        let samples = n as f64 * n as f64;
+        let (mut zn, mut count) = (0.0, 0.0);
+        for j in 0..n {
+            for i in 0..n {
+                let (dx, dy) = if jittered {
+                    (rng.gen::<f64>(), rng.gen::<f64>())
+                } else {
+                    (0.5, 0.5)
+                };
+                let (sample_zn, sample_count) = sample_at(
+                    x as f64 - 0.5 + (i as f64 + dx) / n as f64,
+                    y as f64 - 0.5 + (j as f64 + dy) / n as f64,
+                );
+                zn += sample_zn;
+                count += sample_count;
+            }
+        }
+        PixelIntensity {
+            zn: (zn / samples) as f32,
+            count: (count / samples) as f32,
+        }
+    };
+
+    let pixels = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+    match supersampling {
+        Supersampling::Grid { n } => pixels.map(|(x, y)| average(x, y, n, false)).collect(),
+        Supersampling::Jittered { n } => pixels.map(|(x, y)| average(x, y, n, true)).collect(),
+        Supersampling::Adaptive { n, threshold } => {
+            let single = rasterize(resolution, range.clone(), &sample);
+            // the neighbours outside of the fragment are sampled too, so that the edges
+            // of the fragments are refined as in a single image
+            let count_at = |x: i64, y: i64| {
+                if (0..width).contains(&x) && (0..height).contains(&y) {
+                    single[(y * width + x) as usize].count
+                } else {
+                    sample_at(x as f64, y as f64).1 as f32
+                }
+            };
+            let is_edge = |x: i64, y: i64| {
+                let count = count_at(x, y);
+                [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .any(|(dx, dy)| (count_at(x + dx, y + dy) - count).abs() > threshold)
+            };
+            let edges: Vec<bool> = pixels.clone().map(|(x, y)| is_edge(x, y)).collect();
+            pixels
+                .zip(edges)
+                .map(|((x, y), edge)| {
+                    if edge {
+                        average(x, y, n, false)
+                    } else {
+                        single[(y * width + x) as usize].clone()
+                    }
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::scene::Scene;
+
+/// Signature at the start of every PNG file
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Keyword of the text chunk holding the scene
+const SCENE_KEYWORD: &str = "Scene";
+
+/// Size of the length, type and CRC fields around the data of a chunk
+const CHUNK_OVERHEAD: usize = 12;
+
+/// A chunk of a PNG file: its type and its data
+type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+/// CRC-32 of the PNG chunks (ISO 3309), over the chunk type and data
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Encode a chunk: length, type, data and CRC
+fn chunk(chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len() + CHUNK_OVERHEAD);
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(chunk_type);
+    bytes.extend_from_slice(data);
+    let crc = crc32(&bytes[4..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+    bytes
+}
+
+/// The chunks of a PNG file as (type, data), checking their CRC
+fn chunks(png: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    if png.len() < PNG_SIGNATURE.len() || png[..PNG_SIGNATURE.len()] != PNG_SIGNATURE {
+        return Err("Not a PNG file".to_string());
+    }
+    let mut chunks = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+    while position < png.len() {
+        let header = png
+            .get(position..position + 8)
+            .ok_or("Truncated PNG chunk")?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let chunk_type = [header[4], header[5], header[6], header[7]];
+        let end = position + 8 + length;
+        let crc = png.get(end..end + 4).ok_or("Truncated PNG chunk")?;
+        if crc32(&png[position + 4..end]) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return Err(format!(
+                "Invalid CRC of the {} chunk",
+                String::from_utf8_lossy(&chunk_type)
+            ));
+        }
+        chunks.push((chunk_type, &png[position + 8..end]));
+        position = end + 4;
+    }
+    Ok(chunks)
+}
+
+/// Add the scene as JSON in an iTXt chunk (UTF-8 text, uncompressed) right after the header
+/// chunk of an encoded PNG image
+pub fn embed_scene(png: &[u8], scene: &Scene) -> Result<Vec<u8>, String> {
+    let json =
+        serde_json::to_string(scene).map_err(|err| format!("Cannot serialize scene: {err}"))?;
+    let header = chunks(png)?;
+    if header.first().map(|(chunk_type, _)| chunk_type) != Some(b"IHDR") {
+        return Err("The PNG file does not start with its header".to_string());
+    }
+    let header_end = PNG_SIGNATURE.len() + CHUNK_OVERHEAD + header[0].1.len();
+
+    // keyword, null separator, no compression (flag and method), empty language and
+    // translated keyword, each followed by a null separator, then the text
+    let mut data = Vec::with_capacity(SCENE_KEYWORD.len() + 5 + json.len());
+    data.extend_from_slice(SCENE_KEYWORD.as_bytes());
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    data.extend_from_slice(json.as_bytes());
+
+    let text_chunk = chunk(b"iTXt", &data);
+    let mut bytes = Vec::with_capacity(png.len() + text_chunk.len());
+    bytes.extend_from_slice(&png[..header_end]);
+    bytes.extend_from_slice(&text_chunk);
+    bytes.extend_from_slice(&png[header_end..]);
+    Ok(bytes)
+}
+
+/// Read back the scene of a PNG image, from an uncompressed iTXt or a tEXt chunk
+pub fn read_scene(png: &[u8]) -> Result<Scene, String> {
+    for (chunk_type, data) in chunks(png)? {
+        let text = match &chunk_type {
+            b"iTXt" => itxt_text(data),
+            b"tEXt" => data
+                .strip_prefix(SCENE_KEYWORD.as_bytes())
+                .and_then(|rest| rest.strip_prefix(&[0])),
+            _ => None,
+        };
+        if let Some(text) = text {
+            return serde_json::from_slice(text)
+                .map_err(|err| format!("Invalid scene in the PNG file: {err}"));
+        }
+    }
+    Err("The PNG file holds no scene".to_string())
+}
+
+/// Text of an uncompressed iTXt chunk of the scene keyword
+fn itxt_text(data: &[u8]) -> Option<&[u8]> {
+    let rest = data
+        .strip_prefix(SCENE_KEYWORD.as_bytes())?
+        .strip_prefix(&[0])?;
+    // compressed chunks are not written by `embed_scene`
+    let rest = rest.strip_prefix(&[0, 0])?;
+    let language_end = rest.iter().position(|&byte| byte == 0)?;
+    let rest = &rest[language_end + 1..];
+    let translated_end = rest.iter().position(|&byte| byte == 0)?;
+    Some(&rest[translated_end + 1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+    use super::{chunk, chunks, embed_scene, read_scene};
+    use crate::scene::Scene;
+
+    fn scene() -> Scene {
+        serde_json::from_str(
+            r#"{
+                "fractal": {"Julia": {"c": {"re": 0.285, "im": 0.013}, "divergence_threshold_square": 4.0}},
+                "max_iteration": 64,
+                "resolution": {"nx": 4, "ny": 3},
+                "range": {"min": {"x": -1.2, "y": -1.2}, "max": {"x": 1.2, "y": 1.2}},
+                "center": {"re": "0.1", "im": "-1e-30"},
+                "coloring": {"palette": "ocean", "offset": 0.25}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    /// A small PNG image encoded by the image crate
+    fn png() -> Vec<u8> {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(4, 3))
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_scene_read_back() {
+        let png = embed_scene(&png(), &scene()).unwrap();
+        assert_eq!(read_scene(&png).unwrap(), scene());
+
+        // right after the header, and the image still decodes
+        let types: Vec<[u8; 4]> = chunks(&png).unwrap().iter().map(|(t, _)| *t).collect();
+        assert_eq!(&types[..2], [*b"IHDR", *b"iTXt"]);
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (4, 3));
+    }
+
+    #[test]
+    fn test_scene_in_text_chunk() {
+        let png = png();
+        let json = serde_json::to_string(&scene()).unwrap();
+        let mut data = b"Scene\0".to_vec();
+        data.extend_from_slice(json.as_bytes());
+        // a tEXt chunk written by another tool, before the end of the image
+        let end = png.len() - 12;
+        let with_text = [&png[..end], &chunk(b"tEXt", &data), &png[end..]].concat();
+        assert_eq!(read_scene(&with_text).unwrap(), scene());
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert_eq!(read_scene(b"GIF89a").unwrap_err(), "Not a PNG file");
+        assert_eq!(
+            read_scene(&png()).unwrap_err(),
+            "The PNG file holds no scene"
+        );
+
+        let mut corrupted = embed_scene(&png(), &scene()).unwrap();
+        let last = corrupted.len() - 20;
+        corrupted[last] ^= 1;
+        assert!(read_scene(&corrupted)
+            .unwrap_err()
+            .starts_with("Invalid CRC"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+
+use complex::complex::Complex;
+use complex::complex_dd::ComplexDD;
+use complex::double_double::DoubleDouble;
+use serde::{Deserialize, Serialize};
+
+use crate::color_map::Coloring;
+use crate::message::{
+    FractalDescriptor, FragmentTask, Plane, Point, Range, Resolution, Supersampling,
+};
+use crate::png_metadata;
+
+/// Half-width of the window of a Julia set opened from a point, the escape radius of z^2 + c
+const JULIA_HALF_WIDTH: f64 = 2.0;
+
+/// A scene is everything needed to render a full image: the fractal, the window in the
+/// physical space, the resolution of the whole image and the maximum number of iterations.
+/// It is what the server loads from a JSON scene file before splitting it into fragments.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Scene {
+    pub fractal: FractalDescriptor,
+    pub max_iteration: u16,
+    pub resolution: Resolution,
+    pub range: Range,
+    /// High precision centre of the scene. When it is set, `range` is relative to it, so that
+    /// deep-zoom coordinates keep all their digits in the scene file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<ComplexDD>,
+    /// Plane to draw the fractal in, instead of its own one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plane: Option<Plane>,
+    /// Palette of the image assembled by the server, the cosine palette when it is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coloring: Option<Coloring>,
+    /// Samples per pixel, a single one when it is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersampling: Option<Supersampling>,
+}
+
+impl Scene {
+    /// Load a scene from a JSON file, or from the metadata of a PNG image rendered by the
+    /// project, and check it
+    pub fn load(path: &str) -> Result<Scene, String> {
+        let scene: Scene = if path.ends_with(".png") {
+            let png = fs::read(path).map_err(|err| format!("Cannot read {path}: {err}"))?;
+            png_metadata::read_scene(&png).map_err(|err| format!("Invalid scene {path}: {err}"))?
+        } else {
+            let content =
+                fs::read_to_string(path).map_err(|err| format!("Cannot read {path}: {err}"))?;
+            serde_json::from_str(&content).map_err(|err| format!("Invalid scene {path}: {err}"))?
+        };
+        scene
+            .validate()
+            .map_err(|err| format!("Invalid scene {path}: {err}"))?;
+        Ok(scene)
+    }
+
+    /// The scene of a single fragment, as rendered by a worker
+    pub fn from_task(task: &FragmentTask) -> Scene {
+        Scene {
+            fractal: task.fractal.clone(),
+            max_iteration: task.max_iteration,
+            resolution: task.resolution.clone(),
+            range: task.range.clone(),
+            center: task.center,
+            plane: task.plane,
+            coloring: None,
+            supersampling: task.supersampling,
+        }
+    }
+
+    /// Check the fractal, that it can be drawn in the plane of the scene and supersampled,
+    /// and that its palette exists
+    pub fn validate(&self) -> Result<(), String> {
+        self.fractal.validate()?;
+        self.coloring().color_map()?;
+        if self.plane.is_some() && !self.fractal.has_planes() {
+            return Err(format!(
+                "{:?} cannot be drawn in another plane",
+                self.fractal
+            ));
+        }
+        if let Some(supersampling) = self.supersampling {
+            self.fractal.validate_supersampling(supersampling)?;
+        }
+        Ok(())
+    }
+
+    /// The scene of the Julia set at the point `c` of this scene: the same fractal drawn in its
+    /// dynamic plane, over a window of half-width 2 around 0 keeping the aspect ratio
+    pub fn julia_at(&self, c: Complex) -> Result<Scene, String> {
+        if !self.fractal.has_planes() {
+            return Err(format!("{:?} has no Julia set", self.fractal));
+        }
+        let half_width = JULIA_HALF_WIDTH;
+        let half_height = half_width * self.resolution.ny as f64 / self.resolution.nx as f64;
+        Ok(Scene {
+            range: Range {
+                min: Point {
+                    x: -half_width,
+                    y: -half_height,
+                },
+                max: Point {
+                    x: half_width,
+                    y: half_height,
+                },
+            },
+            center: None,
+            plane: Some(Plane::Dynamic { c }),
+            ..self.clone()
+        })
+    }
+
+    /// The coloring of the image, the default one when it is not set
+    pub fn coloring(&self) -> Coloring {
+        self.coloring.clone().unwrap_or_default()
+    }
+
+    /// Save the scene to a JSON file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|err| format!("Cannot serialize scene: {err}"))?;
+        fs::write(path, content).map_err(|err| format!("Cannot write {path}: {err}"))
+    }
+
+    /// The range in absolute coordinates, with the centre added and rounded to f64, for the
+    /// fractals which do not keep the precision of the centre by perturbation
+    pub fn absolute_range(&self) -> Range {
+        match self.center {
+            Some(center) => Self::absolute_range_around(center, &self.range),
+            None => self.range.clone(),
+        }
+    }
+
+    /// A range relative to `center` in absolute coordinates, rounded to f64
+    pub fn absolute_range_around(center: ComplexDD, range: &Range) -> Range {
+        Range {
+            min: Self::offset_point(center, &range.min),
+            max: Self::offset_point(center, &range.max),
+        }
+    }
+
+    fn offset_point(center: ComplexDD, offset: &Point) -> Point {
+        Point {
+            x: (center.re + DoubleDouble::from(offset.x)).to_f64(),
+            y: (center.im + DoubleDouble::from(offset.y)).to_f64(),
+        }
+    }
+}
@@ -34,8 +34,8 @@ impl<T> ClojureTcpStream<T> {
 /// and return the message and the data
 /// if the message is not a message
 /// then exit the program
-pub fn buffer_to_object(message_buf: &mut Vec<u8>) -> Message {
-    let message = match std::str::from_utf8(&message_buf) {
+pub fn buffer_to_object(message_buf: &mut [u8]) -> Message {
+    let message = match std::str::from_utf8(message_buf) {
         Err(value) => {
             println!("error {value:?}");
             exit(0)
@@ -45,7 +45,7 @@ pub fn buffer_to_object(message_buf: &mut Vec<u8>) -> Message {
             value
         }
     };
-    match serde_json::from_str(&message) {
+    match serde_json::from_str(message) {
         Err(value) => {
             println!("Failed to deserialize JSON message {value:?}");
             exit(0)
@@ -136,37 +136,32 @@ pub fn send_message(
             serialized_bytes,
         ]
         .concat()
+    } else if let Some(data) = &data {
+        [
+            serialized_size_bytes,
+            serialized_size_message_bytes,
+            serialized_bytes,
+            data,
+        ]
+        .concat()
     } else {
-        if let Some(data) = &data {
-            [
-                serialized_size_bytes,
-                serialized_size_message_bytes,
-                serialized_bytes,
-                data,
-            ]
-            .concat()
-        } else {
-            [
-                serialized_size_bytes,
-                serialized_size_message_bytes,
-                serialized_bytes,
-            ]
-            .concat()
-        }
+        [
+            serialized_size_bytes,
+            serialized_size_message_bytes,
+            serialized_bytes,
+        ]
+        .concat()
     };
 
-    if data_not_exists {
-        send_byte_with_tcp_stream(stream, Some(compact));
-        return stream;
-    } else if client == false {
+    if data_not_exists || !client {
         send_byte_with_tcp_stream(stream, Some(compact));
-        return stream;
+        stream
     } else {
         let address = "localhost:8787".to_string();
         match connect_to_server(address) {
             Ok(server) => {
                 send_byte_with_tcp_stream(server, Some(compact));
-                return server;
+                server
             }
             Err(err) => {
                 println!("{}", err);
@@ -178,8 +173,8 @@ pub fn send_message(
 
 /// Display the data as hex for debugging server
 pub fn display_data(data: Vec<u8>) {
-    for i in 0..data.len() {
-        println!("Byte value as hex: {:#02x}", data[i]);
+    for byte in data {
+        println!("Byte value as hex: {:#02x}", byte);
     }
     println!();
 }
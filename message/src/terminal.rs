@@ -0,0 +1,160 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use image::imageops::{self, FilterType};
+use image::{GrayImage, RgbImage};
+
+use crate::drawing_image::color_image;
+use crate::message::PixelIntensity;
+use crate::scene::Scene;
+
+/// Width of the preview when the terminal does not tell its own in `COLUMNS`
+pub const DEFAULT_COLUMNS: u32 = 80;
+
+/// Characters of the ASCII preview, from the darkest to the brightest
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// First braille character, with no dot raised
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/// Bit of each dot of a braille character, by row of 4 and column of 2
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// How an image is drawn in a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TerminalMode {
+    /// Two pixels per character with the upper half block, in 24-bit ANSI colours
+    #[default]
+    TrueColor,
+    /// One character of a brightness ramp per cell, for terminals without colours
+    Ascii,
+    /// Eight dots per braille character, raised where the image is brighter than on average
+    Braille,
+}
+
+impl FromStr for TerminalMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<TerminalMode, String> {
+        match mode {
+            "truecolor" => Ok(TerminalMode::TrueColor),
+            "ascii" => Ok(TerminalMode::Ascii),
+            "braille" => Ok(TerminalMode::Braille),
+            _ => Err(format!(
+                "Unknown terminal mode {mode}, expected truecolor, ascii or braille"
+            )),
+        }
+    }
+}
+
+/// Width of the terminal, from `COLUMNS`, or `DEFAULT_COLUMNS`
+pub fn terminal_columns() -> u32 {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > 0)
+        .unwrap_or(DEFAULT_COLUMNS)
+}
+
+/// Colour the pixel intensities of a scene and draw them `columns` characters wide
+pub fn render_pixels(
+    pixel_intensity_vec: &[PixelIntensity],
+    scene: &Scene,
+    columns: u32,
+    mode: TerminalMode,
+) -> Result<String, String> {
+    let coloring = scene.coloring();
+    let color_map = coloring.color_map()?;
+    let image = color_image(
+        scene.resolution.nx as u32,
+        scene.resolution.ny as u32,
+        pixel_intensity_vec,
+        scene.fractal.color_mode(),
+        &coloring,
+        color_map.as_ref(),
+    );
+    Ok(render(&image, columns, mode))
+}
+
+/// Draw an image `columns` characters wide, keeping its aspect ratio with characters twice as
+/// high as wide. Each character averages the pixels it covers.
+pub fn render(image: &RgbImage, columns: u32, mode: TerminalMode) -> String {
+    let columns = columns.max(1);
+    // rows of characters drawing `across` x `down` square samples each
+    // (a character is twice as high as wide, so `down` is twice `across` for square samples)
+    let height = |across: u32, down: u32| {
+        let samples = (columns * across) as f64 * image.height() as f64 / image.width() as f64;
+        ((samples / down as f64).round() as u32).max(1)
+    };
+    let mut text = String::new();
+    match mode {
+        TerminalMode::TrueColor => {
+            let rows = height(1, 2);
+            let small = imageops::resize(image, columns, rows * 2, FilterType::Triangle);
+            for row in 0..rows {
+                for column in 0..columns {
+                    let [r, g, b] = small.get_pixel(column, row * 2).0;
+                    let [br, bg, bb] = small.get_pixel(column, row * 2 + 1).0;
+                    let _ = write!(
+                        text,
+                        "\x1b[38;2;{r};{g};{b}m\x1b[48;2;{br};{bg};{bb}m\u{2580}"
+                    );
+                }
+                text.push_str("\x1b[0m\n");
+            }
+        }
+        TerminalMode::Ascii => {
+            let rows = height(1, 2);
+            let small = luminance(&imageops::resize(
+                image,
+                columns,
+                rows,
+                FilterType::Triangle,
+            ));
+            for row in 0..rows {
+                for column in 0..columns {
+                    let level = small.get_pixel(column, row).0[0] as usize;
+                    text.push(ASCII_RAMP[level * (ASCII_RAMP.len() - 1) / 255] as char);
+                }
+                text.push('\n');
+            }
+        }
+        TerminalMode::Braille => {
+            let rows = height(2, 4);
+            let small = luminance(&imageops::resize(
+                image,
+                columns * 2,
+                rows * 4,
+                FilterType::Triangle,
+            ));
+            let mean = small.pixels().map(|pixel| pixel.0[0] as u64).sum::<u64>()
+                / (small.width() * small.height()) as u64;
+            for row in 0..rows {
+                for column in 0..columns {
+                    let mut dots = 0;
+                    for (dy, bits) in BRAILLE_DOTS.iter().enumerate() {
+                        for (dx, bit) in bits.iter().enumerate() {
+                            let pixel =
+                                small.get_pixel(column * 2 + dx as u32, row * 4 + dy as u32);
+                            if pixel.0[0] as u64 > mean {
+                                dots |= bit;
+                            }
+                        }
+                    }
+                    text.push(char::from_u32(BRAILLE_BLANK + dots).unwrap_or(' '));
+                }
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+/// Perceived brightness of an image, with the weights of sRGB
+fn luminance(image: &RgbImage) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        let level = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+        image::Luma([level.round().min(255.0) as u8])
+    })
+}
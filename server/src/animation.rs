@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::process::exit;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, RgbImage, RgbaImage};
+use message::animation::Zoom;
+use message::message::{FragmentTask, PixelIntensity, Range, Resolution};
+
+use crate::canvas::Canvas;
+use crate::fragment_task::{RangeManager, RangeManagerTrait};
+use crate::schedule::Schedule;
+use crate::Output;
+
+/// Delay between two frames of the GIF, 25 frames per second
+const GIF_FRAME_DELAY_MS: u32 = 40;
+
+/// A frame whose fragments are being received
+struct FrameProgress {
+    range_manager: RangeManager,
+    canvas: Canvas,
+    received: Vec<bool>,
+}
+
+/// Animated GIF written frame after frame, in order, whatever the order the frames are
+/// finished in
+struct GifWriter {
+    encoder: GifEncoder<File>,
+    next_frame: u32,
+    pending: BTreeMap<u32, RgbaImage>,
+}
+
+impl GifWriter {
+    /// Create the GIF file, looping forever
+    fn create(filename: &str) -> Result<GifWriter, String> {
+        let file =
+            File::create(filename).map_err(|err| format!("Cannot write {filename}: {err}"))?;
+        let mut encoder = GifEncoder::new(file);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|err| format!("Cannot write {filename}: {err}"))?;
+        Ok(GifWriter {
+            encoder,
+            next_frame: 0,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    /// Add a finished frame, and write the frames that are now in order
+    fn push(&mut self, frame: u32, image: RgbImage) -> Result<(), String> {
+        self.pending
+            .insert(frame, DynamicImage::ImageRgb8(image).into_rgba8());
+        while let Some(image) = self.pending.remove(&self.next_frame) {
+            let delay = Delay::from_numer_denom_ms(GIF_FRAME_DELAY_MS, 1);
+            self.encoder
+                .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                .map_err(|err| format!("Cannot write the GIF frame {}: {err}", self.next_frame))?;
+            self.next_frame += 1;
+        }
+        Ok(())
+    }
+}
+
+/// File name of a frame: the output file name with the frame number before its extension,
+/// such as `fractal_0042.png`
+fn frame_filename(filename: &str, frame: u32) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, extension)) => format!("{stem}_{frame:04}.{extension}"),
+        None => format!("{filename}_{frame:04}"),
+    }
+}
+
+/// Render of a zoom: the fragment tasks of all the frames are numbered one frame after the
+/// other, and a frame is saved as soon as all its fragments are back. Once every task has been
+/// handed out, the fragments still missing are handed out again, in case a worker was lost.
+pub struct Animation {
+    zoom: Zoom,
+    output: Output,
+    /// Fragments of the grid of each frame
+    fragments: u32,
+    /// Number of the next task never handed out
+    next_task: u32,
+    /// Frames with fragments handed out and not all received
+    in_progress: BTreeMap<u32, FrameProgress>,
+    saved: u32,
+    gif: Option<GifWriter>,
+}
+
+impl Animation {
+    /// Prepare the render of a zoom saved to numbered files named after the output,
+    /// and to an animated GIF when `gif` is given
+    pub fn new(zoom: Zoom, output: Output, gif: Option<&str>) -> Result<Animation, String> {
+        let fragments = RangeManager::from_scene(zoom.scene.clone())
+            .vec_num_range
+            .len() as u32;
+        let gif = gif.map(GifWriter::create).transpose()?;
+        Ok(Animation {
+            zoom,
+            output,
+            fragments,
+            next_task: 0,
+            in_progress: BTreeMap::new(),
+            saved: 0,
+            gif,
+        })
+    }
+
+    /// The frame being received, created when its first task is handed out
+    fn frame_progress(&mut self, frame: u32) -> &mut FrameProgress {
+        let scene = self.zoom.frame(frame);
+        let fragments = self.fragments as usize;
+        self.in_progress
+            .entry(frame)
+            .or_insert_with(|| match Canvas::from_scene(&scene) {
+                Ok(canvas) => FrameProgress {
+                    range_manager: RangeManager::from_scene(scene),
+                    canvas,
+                    received: vec![false; fragments],
+                },
+                Err(err) => {
+                    println!("{}", err);
+                    exit(1);
+                }
+            })
+    }
+
+    /// Save a complete frame to its file and to the GIF, closing the GIF after the last frame
+    fn save_frame(&mut self, frame: u32, canvas: &Canvas) {
+        let filename = frame_filename(&self.output.filename, frame);
+        canvas.save(&filename, self.output.sixteen_bit);
+        self.output.show_preview(canvas);
+        if let Some(gif) = &mut self.gif {
+            if let Err(err) = gif.push(frame, canvas.image()) {
+                println!("Error: {}", err);
+            }
+        }
+        self.saved += 1;
+        println!(
+            "Frame {}/{} saved to {}",
+            self.saved, self.zoom.frames, filename
+        );
+        if self.is_done() {
+            // the trailer of the GIF is written when the encoder is dropped
+            self.gif = None;
+        }
+    }
+}
+
+impl Schedule for Animation {
+    /// Whether every frame has been saved
+    fn is_done(&self) -> bool {
+        self.saved == self.zoom.frames
+    }
+
+    /// The next task and its number, or None when every frame has been saved
+    fn next_task(&mut self) -> Option<(u32, FragmentTask)> {
+        let number = if self.next_task < self.zoom.frames * self.fragments {
+            self.next_task += 1;
+            self.next_task - 1
+        } else {
+            self.in_progress.iter().find_map(|(frame, progress)| {
+                let fragment = progress.received.iter().position(|received| !received)?;
+                Some(frame * self.fragments + fragment as u32)
+            })?
+        };
+        let fragment = (number % self.fragments) as u8;
+        let task = self
+            .frame_progress(number / self.fragments)
+            .range_manager
+            .get_current_range(fragment);
+        Some((number, task))
+    }
+
+    /// Add the result of the task of the given number, and save its frame when it is complete.
+    /// The results of frames already saved are ignored.
+    fn add_result(
+        &mut self,
+        number: u32,
+        resolution: &Resolution,
+        range: &Range,
+        pixels: &[PixelIntensity],
+    ) {
+        let frame = number / self.fragments;
+        let progress = match self.in_progress.get_mut(&frame) {
+            Some(progress) => progress,
+            None => return,
+        };
+        progress.canvas.add_fragment(resolution, range, pixels);
+        progress.received[(number % self.fragments) as usize] = true;
+        if progress.received.iter().all(|&received| received) {
+            if let Some(progress) = self.in_progress.remove(&frame) {
+                self.save_frame(frame, &progress.canvas);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use image::RgbImage;
+use message::color_map::ColorMap;
+use message::drawing_image::color_image;
+use message::export::export_image;
+use message::frakt::Frakt;
+use message::message::{PixelIntensity, Range, Resolution};
+use message::scene::Scene;
+use message::terminal::{render, TerminalMode};
+
+/// The image of the whole scene, filled with the fragment results of the workers
+pub struct Canvas {
+    pub resolution: Resolution,
+    /// Range of the scene, relative to its centre when it has one, as the ranges of the results
+    pub range: Range,
+    pub pixels: Vec<PixelIntensity>,
+    histogram: bool,
+    /// Number of fragment results added
+    fragments: usize,
+    color_map: Box<dyn ColorMap>,
+    scene: Scene,
+}
+
+impl Canvas {
+    /// Create an empty canvas covering the scene, or tell why its palette cannot be loaded
+    pub fn from_scene(scene: &Scene) -> Result<Canvas, String> {
+        let size = scene.resolution.nx as usize * scene.resolution.ny as usize;
+        let color_map = scene.coloring().color_map()?;
+        Ok(Canvas {
+            resolution: scene.resolution.clone(),
+            range: scene.range.clone(),
+            pixels: vec![
+                PixelIntensity {
+                    zn: 0.0,
+                    count: 0.0
+                };
+                size
+            ],
+            histogram: scene.fractal.is_histogram(),
+            fragments: 0,
+            color_map,
+            scene: scene.clone(),
+        })
+    }
+
+    /// Add the result of a fragment: a tile is copied at its place in the canvas,
+    /// a partial histogram is summed with the others
+    pub fn add_fragment(
+        &mut self,
+        resolution: &Resolution,
+        range: &Range,
+        pixels: &[PixelIntensity],
+    ) {
+        self.fragments += 1;
+        if self.histogram {
+            self.sum_histogram(resolution, pixels);
+        } else {
+            self.paste_tile(resolution, range, pixels);
+        }
+    }
+
+    /// Number of fragment results added so far
+    pub fn fragments(&self) -> usize {
+        self.fragments
+    }
+
+    /// Save the canvas to an image file in the format of its extension,
+    /// raw 16-bit intensities for a PNG when `sixteen_bit` is set
+    pub fn save(&self, filename: &str, sixteen_bit: bool) {
+        if let Err(err) = export_image(
+            filename,
+            sixteen_bit,
+            &self.pixels,
+            &self.scene,
+            self.color_map.as_ref(),
+        ) {
+            println!("Error: {}", err);
+        }
+    }
+
+    /// The coloured image of the canvas
+    pub fn image(&self) -> RgbImage {
+        color_image(
+            self.resolution.nx as u32,
+            self.resolution.ny as u32,
+            &self.pixels,
+            self.scene.fractal.color_mode(),
+            &self.scene.coloring(),
+            self.color_map.as_ref(),
+        )
+    }
+
+    /// The coloured image of the canvas drawn in the terminal, `columns` characters wide, under
+    /// the number of fragments received so far
+    pub fn preview(&self, columns: u32, mode: TerminalMode) -> String {
+        format!(
+            "{} fragments received\n{}",
+            self.fragments,
+            render(&self.image(), columns, mode)
+        )
+    }
+
+    /// Save the raw pixel intensities of the canvas and its scene to a .frakt file,
+    /// to colour it again later
+    pub fn save_frakt(&self, filename: &str) {
+        if let Err(err) = Frakt::write(filename, &self.scene, &self.pixels) {
+            println!("Error: {}", err);
+        }
+    }
+
+    /// Copy a tile at the position of its range, clipped to the canvas
+    fn paste_tile(&mut self, resolution: &Resolution, range: &Range, pixels: &[PixelIntensity]) {
+        let width = self.resolution.nx as usize;
+        let height = self.resolution.ny as usize;
+        let column = ((range.min.x - self.range.min.x) / (self.range.max.x - self.range.min.x)
+            * width as f64)
+            .round() as isize;
+        let row = ((range.min.y - self.range.min.y) / (self.range.max.y - self.range.min.y)
+            * height as f64)
+            .round() as isize;
+
+        for (tile_y, tile_row) in pixels.chunks(resolution.nx as usize).enumerate() {
+            let y = row + tile_y as isize;
+            if y < 0 || y as usize >= height {
+                continue;
+            }
+            for (tile_x, pixel) in tile_row.iter().enumerate() {
+                let x = column + tile_x as isize;
+                if x >= 0 && (x as usize) < width {
+                    self.pixels[y as usize * width + x as usize] = pixel.clone();
+                }
+            }
+        }
+    }
+
+    /// Sum the hits of a partial histogram, then set `zn` to the square root of the hits
+    /// divided by the maximum, as the workers do for their own histogram
+    fn sum_histogram(&mut self, resolution: &Resolution, pixels: &[PixelIntensity]) {
+        if *resolution != self.resolution || pixels.len() != self.pixels.len() {
+            println!("Error: histogram of resolution {resolution:?} does not match the canvas");
+            return;
+        }
+        for (pixel, hits) in self.pixels.iter_mut().zip(pixels) {
+            pixel.count += hits.count;
+        }
+        let max_hits = self
+            .pixels
+            .iter()
+            .fold(1.0f32, |max, pixel| max.max(pixel.count));
+        for pixel in &mut self.pixels {
+            pixel.zn = (pixel.count / max_hits).sqrt();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use message::message::{Buddhabrot, FractalDescriptor, PixelIntensity, Resolution};
+    use message::scene::Scene;
+
+    use super::Canvas;
+    use crate::fragment_task::{default_scene, RangeManager, RangeManagerTrait};
+
+    /// Partial histogram with the given hits
+    fn histogram(hits: &[f32]) -> Vec<PixelIntensity> {
+        hits.iter()
+            .map(|&count| PixelIntensity { zn: 0.0, count })
+            .collect()
+    }
+
+    #[test]
+    fn test_uneven_tiles_fill_the_canvas() {
+        let scene = Scene {
+            resolution: Resolution { nx: 7, ny: 5 },
+            ..default_scene()
+        };
+        let mut canvas = Canvas::from_scene(&scene).unwrap();
+        let range_manager = RangeManager::from_scene(scene);
+        for number in 0..range_manager.vec_num_range.len() {
+            let task = range_manager.get_current_range(number as u8);
+            let size = task.resolution.nx as usize * task.resolution.ny as usize;
+            let pixels = histogram(&vec![number as f32 + 1.0; size]);
+            canvas.add_fragment(&task.resolution, &task.range, &pixels);
+        }
+        assert!(canvas.pixels.iter().all(|pixel| pixel.count > 0.0));
+        assert_eq!(canvas.pixels[0].count, 1.0);
+        assert_eq!(canvas.pixels[7 * 5 - 1].count, 16.0);
+    }
+
+    #[test]
+    fn test_partial_histograms_are_summed() {
+        let resolution = Resolution { nx: 2, ny: 2 };
+        let scene = Scene {
+            fractal: FractalDescriptor::Buddhabrot(Buddhabrot {
+                min_iteration: 0,
+                samples: 100,
+            }),
+            resolution: resolution.clone(),
+            ..default_scene()
+        };
+        let mut canvas = Canvas::from_scene(&scene).unwrap();
+        let range = scene.range.clone();
+        canvas.add_fragment(&resolution, &range, &histogram(&[1.0, 0.0, 4.0, 2.0]));
+        canvas.add_fragment(&resolution, &range, &histogram(&[3.0, 0.0, 12.0, 0.0]));
+
+        let counts: Vec<f32> = canvas.pixels.iter().map(|pixel| pixel.count).collect();
+        assert_eq!(counts, [4.0, 0.0, 16.0, 2.0]);
+        let zns: Vec<f32> = canvas.pixels.iter().map(|pixel| pixel.zn).collect();
+        assert_eq!(zns, [0.5, 0.0, 1.0, (2.0f32 / 16.0).sqrt()]);
+        assert_eq!(canvas.fragments(), 2);
+    }
+
+    #[test]
+    fn test_histogram_of_another_resolution_is_ignored() {
+        let scene = Scene {
+            fractal: FractalDescriptor::Buddhabrot(Buddhabrot {
+                min_iteration: 0,
+                samples: 100,
+            }),
+            resolution: Resolution { nx: 2, ny: 1 },
+            ..default_scene()
+        };
+        let mut canvas = Canvas::from_scene(&scene).unwrap();
+        let range = scene.range.clone();
+        canvas.add_fragment(
+            &Resolution { nx: 1, ny: 2 },
+            &range,
+            &histogram(&[1.0, 1.0]),
+        );
+        assert!(canvas.pixels.iter().all(|pixel| pixel.count == 0.0));
+    }
+}
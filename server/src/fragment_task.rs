@@ -2,6 +2,7 @@ use complex::complex::Complex;
 use message::message::{
     FractalDescriptor, FragmentTask, JuliaDescriptor, Point, Range, Resolution, U8Data,
 };
+use message::scene::Scene;
 
 /// Create identification for the client worker
 pub fn create_identification() -> Vec<u8> {
@@ -13,154 +14,178 @@ pub fn create_identification() -> Vec<u8> {
     identification_vec_u8
 }
 
+/// Identification of the task of the given number, sent to the worker and sent back with its
+/// result: the identification of `create_identification` ending with the number
+pub fn task_identification(number: u32) -> Vec<u8> {
+    let mut identification = create_identification();
+    identification[12..].copy_from_slice(&number.to_be_bytes());
+    identification
+}
+
+/// Number of the task of an identification written by `task_identification`
+pub fn task_number(identification: &[u8]) -> Option<u32> {
+    let bytes = identification.get(12..16)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The task of calculating a fragment of the scene, of the given resolution and range,
+/// relative to the centre of the scene when it has one
+pub fn fragment_task(scene: &Scene, resolution: Resolution, range: Range) -> FragmentTask {
+    FragmentTask {
+        id: U8Data {
+            offset: 0,
+            count: 16,
+        },
+        fractal: scene.fractal.clone(),
+        max_iteration: scene.max_iteration,
+        resolution,
+        range,
+        plane: scene.plane,
+        supersampling: scene.supersampling,
+        center: scene.center,
+    }
+}
+
+/// Number of fragments along each axis of the scene
+const TILES_PER_AXIS: u16 = 4;
+
+/// The scene rendered when the server is started without a scene file
+pub fn default_scene() -> Scene {
+    Scene {
+        fractal: FractalDescriptor::Julia(JuliaDescriptor {
+            c: Complex::new(0.285, 0.013),
+            divergence_threshold_square: 4.0,
+            distance_estimation: false,
+            orbit_trap: None,
+        }),
+        max_iteration: 64,
+        resolution: Resolution { nx: 1200, ny: 1200 },
+        range: Range {
+            min: Point { x: -1.2, y: -1.2 },
+            max: Point { x: 1.2, y: 1.2 },
+        },
+        center: None,
+        plane: None,
+        supersampling: None,
+        coloring: None,
+    }
+}
+
 /// Create the fragment task
 pub struct RangeManager {
     pub vec_num_range: Vec<Range>,
-    pub current_range: u8,
+    /// Resolution of the fragment of the same index in `vec_num_range`
+    pub vec_num_resolution: Vec<Resolution>,
+    pub scene: Scene,
+}
+
+/// First pixel of the tile of the given index along an axis of `size` pixels, and first pixel
+/// after it. The tiles differ by one pixel at most when the size is not a multiple of their number.
+fn tile_pixels(size: u16, index: u16) -> (u16, u16) {
+    let bound = |index: u16| (size as u32 * index as u32 / TILES_PER_AXIS as u32) as u16;
+    (bound(index), bound(index + 1))
 }
 
 /// Trait for the range manager
 pub trait RangeManagerTrait {
-    fn new() -> RangeManager;
+    fn new() -> Self;
+
+    fn from_scene(scene: Scene) -> Self;
 
     fn get_current_range(&self, current: u8) -> FragmentTask;
 }
 
 /// Implementation of the trait for the range manager
 impl RangeManagerTrait for RangeManager {
-    /// Create a new range manager with the default ranges and the current range
+    /// Create a new range manager for the default scene
     fn new() -> RangeManager {
-        let mut vec_intensity: Vec<Range> = Vec::new();
-        vec_intensity.push(Range {
-            min: Point { x: -1.2, y: -1.2 },
-            max: Point { x: -0.6, y: -0.6 },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: -0.6, y: -1.2 },
-            max: Point { x: 0.0, y: -0.6 },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: 0.0, y: -1.2 },
-            max: Point {
-                x: 0.6000000000000001,
-                y: -0.6,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: 0.6000000000000001,
-                y: -1.2,
-            },
-            max: Point { x: 1.2, y: -0.6 },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: -1.2, y: -0.6 },
-            max: Point { x: -0.6, y: 0.0 },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: -0.6, y: -0.6 },
-            max: Point { x: 0.0, y: 0.0 },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: 0.0, y: -0.6 },
-            max: Point {
-                x: 0.6000000000000001,
-                y: 0.0,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: 0.6000000000000001,
-                y: -0.6,
-            },
-            max: Point { x: 1.2, y: 0.0 },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: -1.2, y: 0.0 },
-            max: Point {
-                x: -0.6,
-                y: 0.6000000000000001,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: -0.6, y: 0.0 },
-            max: Point {
-                x: 0.0,
-                y: 0.6000000000000001,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point { x: 0.0, y: 0.0 },
-            max: Point {
-                x: 0.6000000000000001,
-                y: 0.6000000000000001,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: 0.6000000000000001,
-                y: 0.0,
-            },
-            max: Point {
-                x: 1.2,
-                y: 0.6000000000000001,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: -1.2,
-                y: 0.6000000000000001,
-            },
-            max: Point { x: -0.6, y: 1.2 },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: -0.6,
-                y: 0.6000000000000001,
-            },
-            max: Point { x: 0.0, y: 1.2 },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: 0.0,
-                y: 0.6000000000000001,
-            },
-            max: Point {
-                x: 0.6000000000000001,
-                y: 1.2,
-            },
-        });
-        vec_intensity.push(Range {
-            min: Point {
-                x: 0.6000000000000001,
-                y: 0.6000000000000001,
-            },
-            max: Point { x: 1.2, y: 1.2 },
-        });
+        RangeManager::from_scene(default_scene())
+    }
 
-        return RangeManager {
-            vec_num_range: vec_intensity,
-            current_range: 0,
-        };
+    /// Create a range manager splitting the scene in a grid of fragments, line by line.
+    /// The fragments cover every pixel of the scene, and their ranges fall between pixels.
+    fn from_scene(scene: Scene) -> RangeManager {
+        let range = scene.range.clone();
+        let Resolution { nx, ny } = scene.resolution;
+        let pixel_width = (range.max.x - range.min.x) / nx as f64;
+        let pixel_height = (range.max.y - range.min.y) / ny as f64;
+
+        let mut vec_num_range: Vec<Range> = Vec::new();
+        let mut vec_num_resolution: Vec<Resolution> = Vec::new();
+        for row in 0..TILES_PER_AXIS {
+            let (top, bottom) = tile_pixels(ny, row);
+            for column in 0..TILES_PER_AXIS {
+                let (left, right) = tile_pixels(nx, column);
+                vec_num_range.push(Range {
+                    min: Point {
+                        x: range.min.x + pixel_width * left as f64,
+                        y: range.min.y + pixel_height * top as f64,
+                    },
+                    max: Point {
+                        x: range.min.x + pixel_width * right as f64,
+                        y: range.min.y + pixel_height * bottom as f64,
+                    },
+                });
+                vec_num_resolution.push(Resolution {
+                    nx: right - left,
+                    ny: bottom - top,
+                });
+            }
+        }
+
+        RangeManager {
+            vec_num_range,
+            vec_num_resolution,
+            scene,
+        }
     }
 
-    /// Get the current range for the fragment task
+    /// Get the current range for the fragment task.
+    /// A histogram fractal covers the whole scene in every fragment.
     fn get_current_range(&self, current: u8) -> FragmentTask {
-        let fragment_task = FragmentTask {
-            id: U8Data {
-                offset: 0,
-                count: 16,
-            },
-            fractal: FractalDescriptor::Julia {
-                0: JuliaDescriptor {
-                    c: Complex::new(0.285, 0.013),
-                    divergence_threshold_square: 4.0,
-                },
-            },
-            max_iteration: 64,
-            resolution: Resolution { nx: 300, ny: 300 },
-            range: self.vec_num_range[current as usize].clone(),
+        let (resolution, range) = if self.scene.fractal.is_histogram() {
+            (self.scene.resolution.clone(), self.scene.range.clone())
+        } else {
+            let index = current as usize % self.vec_num_range.len();
+            (
+                self.vec_num_resolution[index].clone(),
+                self.vec_num_range[index].clone(),
+            )
         };
-        fragment_task
+        fragment_task(&self.scene, resolution, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use message::message::{Point, Range, Resolution};
+
+    use super::{default_scene, RangeManager, RangeManagerTrait};
+
+    #[test]
+    fn test_fragments_cover_every_pixel() {
+        let scene = message::scene::Scene {
+            resolution: Resolution { nx: 1003, ny: 10 },
+            range: Range {
+                min: Point { x: -2.0, y: -1.0 },
+                max: Point { x: 1.0, y: 1.0 },
+            },
+            ..default_scene()
+        };
+        let range_manager = RangeManager::from_scene(scene);
+        let first_row = &range_manager.vec_num_resolution[..4];
+        assert_eq!(first_row.iter().map(|r| r.nx as u32).sum::<u32>(), 1003);
+        let first_column = range_manager.vec_num_resolution.iter().step_by(4);
+        assert_eq!(first_column.map(|r| r.ny as u32).sum::<u32>(), 10);
+
+        let pixel_width = 3.0 / 1003.0;
+        for (number, resolution) in range_manager.vec_num_resolution.iter().enumerate() {
+            let task = range_manager.get_current_range(number as u8);
+            assert_eq!(task.resolution, *resolution);
+            let width = task.range.max.x - task.range.min.x;
+            assert!((width - pixel_width * resolution.nx as f64).abs() < 1e-12);
+        }
+        let last = &range_manager.vec_num_range[15];
+        assert_eq!(last.max, Point { x: 1.0, y: 1.0 });
     }
 }
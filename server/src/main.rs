@@ -1,28 +1,43 @@
-use message::drawing_image;
+use complex::complex::Complex;
+use std::env;
 use std::net::{TcpListener, TcpStream};
+use std::path::Path;
 use std::process::exit;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
 
+use message::animation::Zoom;
 use message::message::{Message, PixelIntensity};
+use message::scene::Scene;
 use message::send_message::{read_message, send_message};
+use message::terminal::{terminal_columns, TerminalMode};
 
-use crate::fragment_task::{create_identification, RangeManager, RangeManagerTrait};
+use crate::animation::Animation;
+use crate::canvas::Canvas;
+use crate::fragment_task::{create_identification, default_scene, RangeManager, RangeManagerTrait};
+use crate::pyramid::Pyramid;
+use crate::schedule::{listen_schedule, render_locally, Schedule};
 use crate::thread_pool_server::ThreadPool;
 
+mod animation;
+mod canvas;
 mod fragment_task;
+mod pyramid;
+mod schedule;
 mod thread_pool_server;
 
-/// Transform a slice of 4 bytes to a f32
+/// Transform a slice of 4 big endian bytes to a f32
 fn transform_u8_to_f32(bytes: &[u8]) -> f32 {
-    return f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
 }
 
 /// Transform a chunk of 8 bytes to a PixelIntensity
 fn transform_chunk_data_to_pixel_intensity(chunk_data: &[u8]) -> PixelIntensity {
     let (first_half, second_half) = chunk_data.split_at(chunk_data.len() / 2);
-    return PixelIntensity {
+    PixelIntensity {
         zn: transform_u8_to_f32(first_half),
         count: transform_u8_to_f32(second_half),
-    };
+    }
 }
 
 /// Transform a vector of u8 to a vector of PixelIntensity
@@ -31,7 +46,7 @@ fn transform_data_to_vec_pixel_intensity(data: Vec<u8>) -> Vec<PixelIntensity> {
     for chunk in data.chunks(8) {
         result.push(transform_chunk_data_to_pixel_intensity(chunk));
     }
-    return result;
+    result
 }
 
 /// Send a new fragment task to the client for the worker
@@ -50,85 +65,110 @@ fn new_fragment_task(stream: &mut TcpStream, number: u8) {
 /// Generate a new connection
 #[allow(dead_code)]
 fn generate_connect(_stream: &mut TcpStream, tcp_listener: &TcpListener) -> TcpStream {
-    loop {
-        match tcp_listener.accept() {
-            Ok((new_stream, _)) => return new_stream,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                exit(1);
-            }
+    match tcp_listener.accept() {
+        Ok((new_stream, _)) => new_stream,
+        Err(e) => {
+            println!("Error: {:?}", e);
+            exit(1);
         }
     }
 }
 
-fn loop_message(stream: &mut TcpStream, mut number: u8) {
-    loop {
-        let (message_option, data) = read_message(stream);
-        match message_option {
-            Some(message) => match message {
-                Message::FragmentRequest(_fragment_request) => {
-                    let fragment_task = RangeManager::new().get_current_range(number);
-                    send_message(
-                        stream,
-                        Message::FragmentTask(fragment_task),
-                        Some(create_identification()),
-                        false,
-                    );
-                    break;
-                }
-                Message::FragmentResult(fragment_result) => {
-                    let data_vec_pixel_intensity = match data {
-                        Some(data) => data,
-                        None => {
-                            println!("Error: {:?}", data);
-                            exit(1);
-                        }
-                    };
-                    let test = data_vec_pixel_intensity[16..].to_vec();
-                    let pixel_intensity_vec = transform_data_to_vec_pixel_intensity(test);
-                    drawing_image::create_image(
-                        fragment_result.resolution.nx as u32,
-                        fragment_result.resolution.ny as u32,
-                        &pixel_intensity_vec,
-                        "fractal.png".to_string(),
-                    );
-                    number += 1;
-                    let fragment_task = RangeManager::new().get_current_range(number);
-                    send_message(
-                        stream,
-                        Message::FragmentTask(fragment_task),
-                        Some(create_identification()),
-                        false,
-                    );
-                    break;
-                }
-                _ => {
-                    println!("Error: {:?}", message);
-                    exit(1);
+/// Answer the message of a worker connection with the next fragment task of the scene,
+/// adding the fragment result to the canvas when there is one and logging the errors.
+/// The image is saved after each pass over the fragments of the scene.
+fn loop_message(
+    stream: &mut TcpStream,
+    range_manager: &RangeManager,
+    canvas: &Mutex<Canvas>,
+    next_fragment: &AtomicU8,
+    output: &Output,
+) {
+    let (message_option, data) = read_message(stream);
+    match message_option {
+        Some(message) => match message {
+            Message::FragmentRequest(_fragment_request) => {}
+            Message::FragmentResult(fragment_result) => {
+                let data_vec_pixel_intensity = match data {
+                    Some(data) => data,
+                    None => {
+                        println!("Error: {:?}", data);
+                        exit(1);
+                    }
+                };
+                let test = data_vec_pixel_intensity[16..].to_vec();
+                let pixel_intensity_vec = transform_data_to_vec_pixel_intensity(test);
+                let mut canvas = canvas.lock().unwrap();
+                canvas.add_fragment(
+                    &fragment_result.resolution,
+                    &fragment_result.range,
+                    &pixel_intensity_vec,
+                );
+                // saved once every fragment of the scene has been received again
+                if canvas
+                    .fragments()
+                    .is_multiple_of(range_manager.vec_num_range.len())
+                {
+                    canvas.save(&output.filename, output.sixteen_bit);
+                    canvas.save_frakt(&output.frakt_filename());
                 }
-            },
+                output.show_preview(&canvas);
+            }
+            Message::FragmentError(fragment_error) => {
+                println!("Worker error: {}", fragment_error.message);
+            }
             _ => {
-                println!("Error: {:?}", message_option);
+                println!("Error: {:?}", message);
                 exit(1);
             }
+        },
+        _ => {
+            println!("Error: {:?}", message_option);
+            exit(1);
         }
     }
+    let number = next_fragment.fetch_add(1, Ordering::SeqCst);
+    let fragment_task = range_manager.get_current_range(number);
+    send_message(
+        stream,
+        Message::FragmentTask(fragment_task),
+        Some(create_identification()),
+        false,
+    );
 }
 
 /// Listen for new connections and send new fragment tasks
 /// to the clients for the workers with the thread pool
-/// to calculate the fractal
-fn listen() {
+/// to calculate the fractal of the scene, gathered in a shared canvas
+fn listen(scene: Scene, output: Output) {
     let listener = TcpListener::bind("localhost:8787").unwrap();
 
     let pool = ThreadPool::new(16);
-    let number = 0;
+    let canvas = match Canvas::from_scene(&scene) {
+        Ok(canvas) => Arc::new(Mutex::new(canvas)),
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    };
+    let range_manager = Arc::new(RangeManager::from_scene(scene));
+    let next_fragment = Arc::new(AtomicU8::new(0));
     for stream in listener.incoming() {
+        let range_manager = Arc::clone(&range_manager);
+        let canvas = Arc::clone(&canvas);
+        let next_fragment = Arc::clone(&next_fragment);
+        let output = output.clone();
         pool.execute(move || {
             println!("New connection");
             match stream {
                 Ok(mut stream) => {
-                    loop_message(&mut stream, number);
+                    loop_message(
+                        &mut stream,
+                        &range_manager,
+                        &canvas,
+                        &next_fragment,
+                        &output,
+                    );
                 }
                 Err(e) => {
                     println!("Error: {:?}", e);
@@ -139,7 +179,217 @@ fn listen() {
     }
 }
 
+/// Image file the canvas is saved to, unless `--output` is given
+const DEFAULT_OUTPUT_FILE: &str = "fractal.png";
+
+const USAGE: &str = "Usage: server [scene.json|image.png] [--julia-at <re+imi>] \
+[--output <image.png|.ppm|.pgm|.tiff|.exr>] [--16bit] [--preview truecolor|ascii|braille] \
+[--zoom <zoom.json> [--local] [--gif <file.gif>]] \
+[--pyramid <name.dzi> [--size <width>x<height>] [--local]]";
+
+/// Where and how the server saves the image of the scene
+#[derive(Clone)]
+struct Output {
+    /// File name, whose extension gives the format
+    filename: String,
+    /// Save the raw intensities in a 16-bit PNG instead of the coloured image
+    sixteen_bit: bool,
+    /// Draw the image in the terminal each time it changes
+    preview: Option<TerminalMode>,
+}
+
+/// ANSI sequence clearing the terminal and moving the cursor to its top left corner
+const CLEAR_TERMINAL: &str = "\x1b[2J\x1b[H";
+
+impl Output {
+    /// File name of the raw intensities, the image file name with the `.frakt` extension
+    fn frakt_filename(&self) -> String {
+        Path::new(&self.filename)
+            .with_extension("frakt")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Redraw the canvas over the whole terminal, when the preview is on
+    fn show_preview(&self, canvas: &Canvas) {
+        if let Some(mode) = self.preview {
+            print!(
+                "{}{}",
+                CLEAR_TERMINAL,
+                canvas.preview(terminal_columns(), mode)
+            );
+        }
+    }
+}
+
+/// Scene file written when the server renders the Julia set at a point of a scene
+const JULIA_SCENE_FILE: &str = "julia.json";
+
+/// The scene of the Julia set at the point of the scene written as a complex number,
+/// such as `-0.8+0.156i`, saved to julia.json to be reopened later
+fn julia_scene(scene: &Scene, point: &str) -> Scene {
+    let c: Complex = match point.parse() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("Invalid point: {}", err);
+            exit(1);
+        }
+    };
+    match scene.julia_at(c) {
+        Ok(julia) => {
+            match julia.save(JULIA_SCENE_FILE) {
+                Ok(()) => println!("Julia set at {} saved to {}", c, JULIA_SCENE_FILE),
+                Err(err) => println!("{}", err),
+            }
+            julia
+        }
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Render the frames of a zoom, numbered after the output file, by the workers or in this
+/// process with `local`, and gather them in an animated GIF when `gif` is given
+fn animate(zoom_path: &str, output: Output, local: bool, gif: Option<String>) {
+    let animation =
+        match Zoom::load(zoom_path).and_then(|zoom| Animation::new(zoom, output, gif.as_deref())) {
+            Ok(animation) => animation,
+            Err(err) => {
+                println!("{}", err);
+                exit(1);
+            }
+        };
+    run_schedule(animation, local);
+}
+
+/// Render the scene at the full resolution `size` (the resolution of the scene by default),
+/// written as a Deep Zoom Image pyramid of tiles, by the workers or in this process with `local`
+fn build_pyramid(scene: Scene, dzi: &str, size: Option<String>, local: bool) {
+    let (width, height) = match size {
+        Some(size) => match parse_size(&size) {
+            Some(size) => size,
+            None => {
+                println!("Invalid size {}, expected <width>x<height>", size);
+                exit(1);
+            }
+        },
+        None => (scene.resolution.nx as u32, scene.resolution.ny as u32),
+    };
+    match Pyramid::new(scene, dzi, width, height) {
+        Ok(pyramid) => run_schedule(pyramid, local),
+        Err(err) => {
+            println!("{}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Parse a size written `<width>x<height>`, such as `65536x65536`
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Calculate the tasks of a schedule in this process with `local`, or by the workers
+fn run_schedule(schedule: impl Schedule + 'static, local: bool) {
+    if local {
+        render_locally(schedule);
+    } else {
+        listen_schedule(schedule);
+    }
+}
+
 /// Main function to start the server
+/// with the scene file given as argument, or the default scene.
+/// With `--julia-at <c>`, the server renders the Julia set at the point c of the scene instead.
+/// With `--zoom <zoom.json>`, it renders the frames of a zoom animation instead.
+/// With `--pyramid <name.dzi>`, it writes the scene as a pyramid of tiles instead of an image.
+/// The image is saved to `--output` (fractal.png by default) in the format of its extension.
 fn main() {
-    listen();
+    let mut scene_path = None;
+    let mut julia_point = None;
+    let mut zoom_path = None;
+    let mut gif = None;
+    let mut pyramid = None;
+    let mut size = None;
+    let mut local = false;
+    let mut output = Output {
+        filename: DEFAULT_OUTPUT_FILE.to_string(),
+        sixteen_bit: false,
+        preview: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--julia-at" | "--output" | "--zoom" | "--gif" | "--pyramid" | "--size"
+            | "--preview" => match args.next() {
+                Some(value) if arg == "--julia-at" => julia_point = Some(value),
+                Some(value) if arg == "--zoom" => zoom_path = Some(value),
+                Some(value) if arg == "--gif" => gif = Some(value),
+                Some(value) if arg == "--pyramid" => pyramid = Some(value),
+                Some(value) if arg == "--size" => size = Some(value),
+                Some(value) if arg == "--preview" => match value.parse() {
+                    Ok(mode) => output.preview = Some(mode),
+                    Err(err) => {
+                        println!("{}", err);
+                        exit(1);
+                    }
+                },
+                Some(value) => output.filename = value,
+                None => {
+                    println!("{}", USAGE);
+                    exit(1);
+                }
+            },
+            "--16bit" => output.sixteen_bit = true,
+            "--local" => local = true,
+            _ => scene_path = Some(arg),
+        }
+    }
+
+    if let Some(zoom_path) = zoom_path {
+        animate(&zoom_path, output, local, gif);
+        return;
+    }
+
+    let scene = match scene_path {
+        Some(path) => match Scene::load(&path) {
+            Ok(scene) => scene,
+            Err(err) => {
+                println!("{}", err);
+                exit(1);
+            }
+        },
+        None => default_scene(),
+    };
+    let scene = match julia_point {
+        Some(point) => julia_scene(&scene, &point),
+        None => scene,
+    };
+    match pyramid {
+        Some(dzi) => build_pyramid(scene, &dzi, size, local),
+        None => listen(scene, output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Output;
+
+    #[test]
+    fn test_frakt_file_follows_the_output() {
+        let output = |filename: &str| Output {
+            filename: filename.to_string(),
+            sixteen_bit: false,
+            preview: None,
+        };
+        assert_eq!(output("fractal.png").frakt_filename(), "fractal.frakt");
+        assert_eq!(
+            output("out/deep.zoom.exr").frakt_filename(),
+            "out/deep.zoom.frakt"
+        );
+        assert_eq!(output("image").frakt_filename(), "image.frakt");
+    }
 }
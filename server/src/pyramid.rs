@@ -0,0 +1,419 @@
+use std::collections::HashMap;
+use std::fs;
+
+use image::imageops::{self, FilterType};
+use image::RgbImage;
+use message::color_map::{ColorMap, Coloring};
+use message::drawing_image::color_image;
+use message::message::{FragmentTask, PixelIntensity, Point, Range, Resolution};
+use message::scene::Scene;
+
+use crate::fragment_task::fragment_task;
+use crate::schedule::Schedule;
+
+/// Width and height of the tiles of the pyramid, the usual size of Deep Zoom images
+const TILE_SIZE: u32 = 256;
+
+/// Full-resolution image of a scene cut into tiles, written as a Deep Zoom Image (DZI) pyramid:
+/// `name.dzi` describes the image and `name_files/<level>/<column>_<row>.png` holds the tiles,
+/// from level 0 (one pixel) to the full resolution, each level half the size of the next one.
+///
+/// Every tile of the full resolution is a fragment task, so that the size of the image is not
+/// limited by the `u16` resolution of a task. The tiles are handed out in Z-order, so that the
+/// four tiles under a tile of the level below are finished together: the lower tile is then
+/// downsampled from them, and they are dropped.
+pub struct Pyramid {
+    scene: Scene,
+    coloring: Coloring,
+    color_map: Box<dyn ColorMap>,
+    /// Folder of the tiles, `name_files`
+    folder: String,
+    width: u32,
+    height: u32,
+    /// Level of the full resolution
+    max_level: u32,
+    /// Next position in Z-order of a tile of the full resolution never handed out
+    next_tile: u32,
+    received: Vec<bool>,
+    /// Tiles written and kept until the other tiles over the same lower tile are written
+    pending: HashMap<(u32, u32, u32), RgbImage>,
+    /// Whether the tile of level 0 has been written
+    done: bool,
+    /// Tiles that could not be written, with the reason
+    failures: Vec<String>,
+}
+
+impl Pyramid {
+    /// Prepare the pyramid of the scene at the given full resolution, writing `name.dzi`
+    pub fn new(scene: Scene, dzi: &str, width: u32, height: u32) -> Result<Pyramid, String> {
+        if scene.fractal.is_histogram() {
+            return Err(format!("{:?} cannot be cut into tiles", scene.fractal));
+        }
+        let coloring = scene.coloring();
+        if coloring.equalize {
+            return Err(
+                "The equalization needs the whole image and cannot be used in tiles".into(),
+            );
+        }
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid pyramid size {width}x{height}"));
+        }
+        let stem = dzi.strip_suffix(".dzi").unwrap_or(dzi);
+        let descriptor = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" \
+             TileSize=\"{TILE_SIZE}\" Overlap=\"0\" Format=\"png\">\n  \
+             <Size Width=\"{width}\" Height=\"{height}\"/>\n\
+             </Image>\n"
+        );
+        fs::write(format!("{stem}.dzi"), descriptor)
+            .map_err(|err| format!("Cannot write {stem}.dzi: {err}"))?;
+
+        let max_level = width.max(height).next_power_of_two().trailing_zeros();
+        let tiles = (width.div_ceil(TILE_SIZE) * height.div_ceil(TILE_SIZE)) as usize;
+        Ok(Pyramid {
+            color_map: coloring.color_map()?,
+            coloring,
+            scene,
+            folder: format!("{stem}_files"),
+            width,
+            height,
+            max_level,
+            next_tile: 0,
+            received: vec![false; tiles],
+            pending: HashMap::new(),
+            done: false,
+            failures: Vec::new(),
+        })
+    }
+
+    /// Width and height in pixels of a level
+    fn level_size(&self, level: u32) -> (u32, u32) {
+        let shift = self.max_level - level;
+        (
+            self.width.div_ceil(1 << shift),
+            self.height.div_ceil(1 << shift),
+        )
+    }
+
+    /// Number of columns and rows of tiles of a level
+    fn level_tiles(&self, level: u32) -> (u32, u32) {
+        let (width, height) = self.level_size(level);
+        (width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE))
+    }
+
+    /// The tile of the full resolution at a position in Z-order, if the image covers it
+    fn tile_at(&self, position: u32) -> Option<(u32, u32)> {
+        let (columns, rows) = self.level_tiles(self.max_level);
+        let (column, row) = deinterleave(position);
+        (column < columns && row < rows).then_some((column, row))
+    }
+
+    /// Number of positions in Z-order, covering a square of a power of two of tiles
+    fn positions(&self) -> u32 {
+        let (columns, rows) = self.level_tiles(self.max_level);
+        let side = columns.max(rows).next_power_of_two();
+        side * side
+    }
+
+    /// The fragment task of a tile of the full resolution
+    fn tile_task(&self, column: u32, row: u32) -> FragmentTask {
+        let range = &self.scene.range;
+        let x0 = column * TILE_SIZE;
+        let y0 = row * TILE_SIZE;
+        let x1 = (x0 + TILE_SIZE).min(self.width);
+        let y1 = (y0 + TILE_SIZE).min(self.height);
+        let point = |x: u32, y: u32| Point {
+            x: range.min.x + x as f64 / self.width as f64 * (range.max.x - range.min.x),
+            y: range.min.y + y as f64 / self.height as f64 * (range.max.y - range.min.y),
+        };
+        fragment_task(
+            &self.scene,
+            Resolution {
+                nx: (x1 - x0) as u16,
+                ny: (y1 - y0) as u16,
+            },
+            Range {
+                min: point(x0, y0),
+                max: point(x1, y1),
+            },
+        )
+    }
+
+    /// Write a tile, then the tile of the level below once the tiles over it are all written
+    fn write_tile(&mut self, level: u32, column: u32, row: u32, image: RgbImage) {
+        let folder = format!("{}/{}", self.folder, level);
+        let path = format!("{folder}/{column}_{row}.png");
+        if let Err(err) = fs::create_dir_all(&folder)
+            .map_err(|err| err.to_string())
+            .and_then(|()| image.save(&path).map_err(|err| err.to_string()))
+        {
+            println!("Error: cannot write {path}: {err}");
+            self.failures.push(format!("{path}: {err}"));
+        }
+        if level == 0 {
+            self.done = true;
+            if self.failures.is_empty() {
+                println!("Pyramid complete in {}", self.folder);
+            }
+            return;
+        }
+        self.pending.insert((level, column, row), image);
+
+        let (columns, rows) = self.level_tiles(level);
+        let (lower_column, lower_row) = (column / 2, row / 2);
+        let children: Vec<(u32, u32)> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .iter()
+            .map(|(dx, dy)| (lower_column * 2 + dx, lower_row * 2 + dy))
+            .filter(|&(column, row)| column < columns && row < rows)
+            .collect();
+        if children
+            .iter()
+            .all(|&(column, row)| self.pending.contains_key(&(level, column, row)))
+        {
+            let lower = self.downsample(level, &children);
+            self.write_tile(level - 1, lower_column, lower_row, lower);
+        }
+    }
+
+    /// Tile of the level below the given one, from the tiles over it, which are dropped
+    fn downsample(&mut self, level: u32, children: &[(u32, u32)]) -> RgbImage {
+        let (first_column, first_row) = children[0];
+        let mut width = 0;
+        let mut height = 0;
+        let tiles: Vec<(u32, u32, RgbImage)> = children
+            .iter()
+            .filter_map(|&(column, row)| {
+                let image = self.pending.remove(&(level, column, row))?;
+                let x = (column - first_column) * TILE_SIZE;
+                let y = (row - first_row) * TILE_SIZE;
+                width = width.max(x + image.width());
+                height = height.max(y + image.height());
+                Some((x, y, image))
+            })
+            .collect();
+        let mut joined = RgbImage::new(width, height);
+        for (x, y, image) in &tiles {
+            imageops::replace(&mut joined, image, *x as i64, *y as i64);
+        }
+        imageops::resize(
+            &joined,
+            width.div_ceil(2),
+            height.div_ceil(2),
+            FilterType::Triangle,
+        )
+    }
+}
+
+impl Schedule for Pyramid {
+    /// The next tile of the full resolution in Z-order, then the tiles still missing
+    fn next_task(&mut self) -> Option<(u32, FragmentTask)> {
+        while self.next_tile < self.positions() {
+            let position = self.next_tile;
+            self.next_tile += 1;
+            if let Some((column, row)) = self.tile_at(position) {
+                return Some((position, self.tile_task(column, row)));
+            }
+        }
+        let (columns, _) = self.level_tiles(self.max_level);
+        let missing = self.received.iter().position(|received| !received)? as u32;
+        let (column, row) = (missing % columns, missing / columns);
+        Some((interleave(column, row), self.tile_task(column, row)))
+    }
+
+    /// Colour the tile of the task, write it and the tiles of the lower levels it completes.
+    /// The tiles already written are ignored.
+    fn add_result(
+        &mut self,
+        number: u32,
+        resolution: &Resolution,
+        _range: &Range,
+        pixels: &[PixelIntensity],
+    ) {
+        let (column, row) = match self.tile_at(number) {
+            Some(tile) => tile,
+            None => return,
+        };
+        let (columns, _) = self.level_tiles(self.max_level);
+        let index = (row * columns + column) as usize;
+        if self.received[index] {
+            return;
+        }
+        self.received[index] = true;
+        let image = color_image(
+            resolution.nx as u32,
+            resolution.ny as u32,
+            pixels,
+            self.scene.fractal.color_mode(),
+            &self.coloring,
+            self.color_map.as_ref(),
+        );
+        self.write_tile(self.max_level, column, row, image);
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The tiles that could not be written, if any
+    fn outcome(&self) -> Result<(), String> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+        Err(format!(
+            "Pyramid incomplete, {} tiles could not be written:\n{}",
+            self.failures.len(),
+            self.failures.join("\n")
+        ))
+    }
+}
+
+/// Position in Z-order of a tile: the bits of its column and row interleaved
+fn interleave(column: u32, row: u32) -> u32 {
+    (0..16).fold(0, |position, bit| {
+        position | ((column >> bit) & 1) << (2 * bit) | ((row >> bit) & 1) << (2 * bit + 1)
+    })
+}
+
+/// Column and row of a position in Z-order
+fn deinterleave(position: u32) -> (u32, u32) {
+    (0..16).fold((0, 0), |(column, row), bit| {
+        (
+            column | ((position >> (2 * bit)) & 1) << bit,
+            row | ((position >> (2 * bit + 1)) & 1) << bit,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use image::RgbImage;
+    use message::message::PixelIntensity;
+
+    use super::{deinterleave, interleave, Pyramid, TILE_SIZE};
+    use crate::fragment_task::default_scene;
+    use crate::schedule::Schedule;
+
+    /// A pyramid of the default scene written in a temporary folder named after the test
+    fn pyramid(name: &str, width: u32, height: u32) -> Pyramid {
+        let dzi = env::temp_dir().join(format!("pyramid_{}_{name}.dzi", std::process::id()));
+        Pyramid::new(default_scene(), dzi.to_str().unwrap(), width, height).unwrap()
+    }
+
+    /// Remove the files of a pyramid
+    fn remove(pyramid: &Pyramid) {
+        let _ = fs::remove_dir_all(&pyramid.folder);
+        let stem = pyramid.folder.strip_suffix("_files").unwrap();
+        let _ = fs::remove_file(format!("{stem}.dzi"));
+    }
+
+    #[test]
+    fn test_z_order() {
+        assert_eq!(interleave(1, 0), 1);
+        assert_eq!(interleave(0, 1), 2);
+        assert_eq!(interleave(2, 1), 6);
+        assert_eq!(interleave(3, 3), 15);
+        for column in 0..40 {
+            for row in 0..40 {
+                assert_eq!(deinterleave(interleave(column, row)), (column, row));
+            }
+        }
+        assert_eq!(deinterleave(u32::MAX), (0xFFFF, 0xFFFF));
+    }
+
+    #[test]
+    fn test_level_sizes() {
+        let pyramid = pyramid("levels", 1000, 300);
+        assert_eq!(pyramid.max_level, 10);
+        assert_eq!(pyramid.level_size(10), (1000, 300));
+        assert_eq!(pyramid.level_size(9), (500, 150));
+        assert_eq!(pyramid.level_size(2), (4, 2));
+        assert_eq!(pyramid.level_size(1), (2, 1));
+        assert_eq!(pyramid.level_size(0), (1, 1));
+        assert_eq!(pyramid.level_tiles(10), (4, 2));
+        assert_eq!(pyramid.positions(), 16);
+        remove(&pyramid);
+    }
+
+    #[test]
+    fn test_downsample_uneven_edge_tiles() {
+        let mut pyramid = pyramid("downsample", TILE_SIZE + 45, TILE_SIZE + 5);
+        let sizes = [
+            ((0, 0), (TILE_SIZE, TILE_SIZE)),
+            ((1, 0), (45, TILE_SIZE)),
+            ((0, 1), (TILE_SIZE, 5)),
+            ((1, 1), (45, 5)),
+        ];
+        for ((column, row), (width, height)) in sizes {
+            let image = RgbImage::from_pixel(width, height, image::Rgb([200, 100, 50]));
+            pyramid.pending.insert((9, column, row), image);
+        }
+        let children: Vec<(u32, u32)> = sizes.iter().map(|&(tile, _)| tile).collect();
+        let lower = pyramid.downsample(9, &children);
+        assert_eq!(
+            lower.dimensions(),
+            ((TILE_SIZE + 45).div_ceil(2), (TILE_SIZE + 5).div_ceil(2))
+        );
+        assert!(lower.pixels().all(|pixel| pixel.0 == [200, 100, 50]));
+        assert!(pyramid.pending.is_empty());
+        remove(&pyramid);
+    }
+
+    #[test]
+    fn test_every_level_is_written() {
+        let mut pyramid = pyramid("levels_written", 300, 20);
+        while let Some((number, task)) = pyramid.next_task() {
+            let size = task.resolution.nx as usize * task.resolution.ny as usize;
+            let pixels = vec![
+                PixelIntensity {
+                    zn: 0.5,
+                    count: 0.5
+                };
+                size
+            ];
+            pyramid.add_result(number, &task.resolution, &task.range, &pixels);
+            if pyramid.is_done() {
+                break;
+            }
+        }
+        assert!(pyramid.outcome().is_ok());
+        for (level, tiles) in [(9, 2), (8, 1), (0, 1)] {
+            let folder = format!("{}/{level}", pyramid.folder);
+            assert_eq!(
+                fs::read_dir(folder).unwrap().count(),
+                tiles,
+                "level {level}"
+            );
+        }
+        let top = image::open(format!("{}/0/0_0.png", pyramid.folder)).unwrap();
+        assert_eq!((top.width(), top.height()), (1, 1));
+        remove(&pyramid);
+    }
+
+    #[test]
+    fn test_write_failures_are_reported() {
+        let mut pyramid = pyramid("failures", 2, 2);
+        // a folder under a file cannot be created
+        let stem = pyramid.folder.strip_suffix("_files").unwrap().to_string();
+        pyramid.folder = format!("{stem}.dzi/files");
+        let (number, task) = pyramid.next_task().unwrap();
+        let pixels = vec![
+            PixelIntensity {
+                zn: 0.5,
+                count: 0.5
+            };
+            4
+        ];
+        pyramid.add_result(number, &task.resolution, &task.range, &pixels);
+        assert!(pyramid.is_done());
+        let error = pyramid.outcome().unwrap_err();
+        assert!(
+            error.starts_with("Pyramid incomplete, 2 tiles could not be written"),
+            "{error}"
+        );
+        let _ = fs::remove_file(format!("{stem}.dzi"));
+    }
+}
@@ -0,0 +1,126 @@
+use std::net::{TcpListener, TcpStream};
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+
+use message::message::{FragmentTask, Message, PixelIntensity, Range, Resolution};
+use message::send_message::{read_message, send_message};
+
+use crate::fragment_task::{task_identification, task_number};
+use crate::thread_pool_server::ThreadPool;
+use crate::transform_data_to_vec_pixel_intensity;
+
+/// Render split in numbered fragment tasks, which ends once the result of every task is back.
+/// The number of a task is sent in its identification, so that its result finds its place.
+pub trait Schedule: Send {
+    /// The next task and its number, or None when the render is done
+    fn next_task(&mut self) -> Option<(u32, FragmentTask)>;
+
+    /// Add the result of the task of the given number
+    fn add_result(
+        &mut self,
+        number: u32,
+        resolution: &Resolution,
+        range: &Range,
+        pixels: &[PixelIntensity],
+    );
+
+    /// Whether the result of every task is back
+    fn is_done(&self) -> bool;
+
+    /// Once done, what went wrong during the render, such as files that could not be written
+    fn outcome(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Calculate every task of the schedule in this process, one fragment after the other, without
+/// saving the image of every fragment as the workers do
+pub fn render_locally(mut schedule: impl Schedule) {
+    while let Some((number, task)) = schedule.next_task() {
+        match task.calculate_pixels() {
+            Ok(pixels) => schedule.add_result(number, &task.resolution, &task.range, &pixels),
+            Err(err) => {
+                println!("{}", err);
+                exit(1);
+            }
+        }
+    }
+    if let Err(err) = schedule.outcome() {
+        println!("{}", err);
+        exit(1);
+    }
+}
+
+/// Answer the message of a worker connection with the next task of the schedule, adding the
+/// fragment result when there is one. The server stops once the schedule is done.
+fn loop_schedule_message(stream: &mut TcpStream, schedule: &Mutex<impl Schedule>) {
+    let (message_option, data) = read_message(stream);
+    match message_option {
+        Some(Message::FragmentRequest(_fragment_request)) => {}
+        Some(Message::FragmentResult(fragment_result)) => {
+            let data = data.unwrap_or_default();
+            match task_number(&data) {
+                Some(number) => {
+                    let pixels = transform_data_to_vec_pixel_intensity(data[16..].to_vec());
+                    schedule.lock().unwrap().add_result(
+                        number,
+                        &fragment_result.resolution,
+                        &fragment_result.range,
+                        &pixels,
+                    );
+                }
+                None => println!("Error: result without identification"),
+            }
+        }
+        Some(Message::FragmentError(fragment_error)) => {
+            println!("Worker error: {}", fragment_error.message);
+        }
+        message_option => {
+            println!("Error: {:?}", message_option);
+            exit(1);
+        }
+    }
+
+    let mut schedule = schedule.lock().unwrap();
+    if schedule.is_done() {
+        match schedule.outcome() {
+            Ok(()) => {
+                println!("Render complete");
+                exit(0);
+            }
+            Err(err) => {
+                println!("{}", err);
+                exit(1);
+            }
+        }
+    }
+    if let Some((number, fragment_task)) = schedule.next_task() {
+        send_message(
+            stream,
+            Message::FragmentTask(fragment_task),
+            Some(task_identification(number)),
+            false,
+        );
+    }
+}
+
+/// Listen for workers and hand them the tasks of the schedule in order
+pub fn listen_schedule(schedule: impl Schedule + 'static) {
+    let listener = TcpListener::bind("localhost:8787").unwrap();
+
+    let pool = ThreadPool::new(16);
+    let schedule = Arc::new(Mutex::new(schedule));
+    for stream in listener.incoming() {
+        let schedule = Arc::clone(&schedule);
+        pool.execute(move || {
+            println!("New connection");
+            match stream {
+                Ok(mut stream) => loop_schedule_message(&mut stream, &schedule),
+                Err(e) => {
+                    println!("Error: {:?}", e);
+                    exit(1);
+                }
+            }
+        });
+    }
+}